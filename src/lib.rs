@@ -8,7 +8,7 @@ use worker::{event, Env, Result, ScheduleContext, ScheduledEvent};
 
 use crate::{
   lil_nouns::LilNouns,
-  meta_gov::MetaGov,
+  meta_gov::{MetaGov, Space},
   prop_house::PropHouse,
   prop_lot::PropLot,
   second_market::SecondMarket,
@@ -36,12 +36,19 @@ async fn start(event: &ScheduledEvent, env: &Env) -> Result<()> {
       };
 
       if env.var("META_GOV_ENABLED")?.to_string() == "true" {
-        match MetaGov::new_from_env(env) {
-          Ok(result) => match result.start().await {
-            Ok(_) => info!("MetaGov started successfully"),
-            Err(error) => error!("Failed to start MetaGov: {:?}", error),
-          },
-          Err(error) => error!("Failed to create MetaGov: {:?}", error),
+        match Space::list_from_env(env) {
+          Ok(spaces) => {
+            for space in &spaces {
+              match MetaGov::new_from_env(env, space) {
+                Ok(result) => match result.start().await {
+                  Ok(_) => info!("MetaGov started successfully for {}", space.id),
+                  Err(error) => error!("Failed to start MetaGov for {}: {:?}", space.id, error),
+                },
+                Err(error) => error!("Failed to create MetaGov for {}: {:?}", space.id, error),
+              }
+            }
+          }
+          Err(error) => error!("Failed to load MetaGov spaces: {:?}", error),
         }
       };
 
@@ -64,8 +71,44 @@ async fn start(event: &ScheduledEvent, env: &Env) -> Result<()> {
           Err(error) => error!("Failed to create PropLot: {:?}", error),
         }
       }
+
+      if env.var("SECOND_MARKET_ENABLED")?.to_string() == "true"
+        && env.var("SECOND_MARKET_SALES_ENABLED")?.to_string() == "true"
+      {
+        match SecondMarket::new_from_env(env) {
+          Ok(result) => match result.check_sales().await {
+            Ok(_) => info!("SecondMarket sales checked successfully"),
+            Err(error) => error!("Failed to check SecondMarket sales: {:?}", error),
+          },
+          Err(error) => error!("Failed to create SecondMarket: {:?}", error),
+        }
+      }
+
+      if env.var("SECOND_MARKET_ENABLED")?.to_string() == "true"
+        && env.var("SECOND_MARKET_ORDERS_ENABLED")?.to_string() == "true"
+      {
+        match SecondMarket::new_from_env(env) {
+          Ok(result) => match result.check_orders().await {
+            Ok(_) => info!("SecondMarket orders checked successfully"),
+            Err(error) => error!("Failed to check SecondMarket orders: {:?}", error),
+          },
+          Err(error) => error!("Failed to create SecondMarket: {:?}", error),
+        }
+      }
     }
     "0 0 * * *" => {
+      if env.var("PROP_HOUSE_ENABLED")?.to_string() == "true"
+        && env.var("PROP_HOUSE_STANDINGS_ENABLED")?.to_string() == "true"
+      {
+        match PropHouse::new_from_env(env) {
+          Ok(result) => match result.post_standings().await {
+            Ok(_) => info!("PropHouse standings posted successfully"),
+            Err(error) => error!("Failed to post PropHouse standings: {:?}", error),
+          },
+          Err(error) => error!("Failed to create PropHouse: {:?}", error),
+        }
+      }
+
       if env.var("SECOND_MARKET_ENABLED")?.to_string() == "true" {
         match SecondMarket::new_from_env(env) {
           Ok(result) => match result.start().await {
@@ -75,6 +118,30 @@ async fn start(event: &ScheduledEvent, env: &Env) -> Result<()> {
           Err(error) => error!("Failed to create SecondMarket: {:?}", error),
         }
       }
+
+      if env.var("SECOND_MARKET_ENABLED")?.to_string() == "true"
+        && env.var("SECOND_MARKET_SUMMARY_ENABLED")?.to_string() == "true"
+      {
+        match SecondMarket::new_from_env(env) {
+          Ok(result) => match result.post_summary().await {
+            Ok(_) => info!("SecondMarket summary posted successfully"),
+            Err(error) => error!("Failed to post SecondMarket summary: {:?}", error),
+          },
+          Err(error) => error!("Failed to create SecondMarket: {:?}", error),
+        }
+      }
+    }
+    "0 0 * * 1"
+      if env.var("PROP_LOT_ENABLED")?.to_string() == "true"
+        && env.var("PROP_LOT_DIGEST_ENABLED")?.to_string() == "true" =>
+    {
+      match PropLot::new_from_env(env) {
+        Ok(result) => match result.post_digest().await {
+          Ok(_) => info!("PropLot digest posted successfully"),
+          Err(error) => error!("Failed to post PropLot digest: {:?}", error),
+        },
+        Err(error) => error!("Failed to create PropLot: {:?}", error),
+      }
     }
     _ => {}
   }
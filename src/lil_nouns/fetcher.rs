@@ -55,6 +55,19 @@ impl GraphQLFetcher {
   }
 
   pub async fn fetch_proposals(&self) -> Option<Vec<Proposal>> {
+    let proposals = self
+      .fetch_proposals_with_descriptions()
+      .await?
+      .into_iter()
+      .map(|(proposal, _)| proposal)
+      .collect();
+
+    Some(proposals)
+  }
+
+  /// Fetches the proposals together with their descriptions, which are too
+  /// large to cache with the proposals.
+  pub async fn fetch_proposals_with_descriptions(&self) -> Option<Vec<(Proposal, String)>> {
     let variables = proposal_and_vote_query::Variables {};
 
     let response = self.fetch::<ProposalAndVoteQuery>(variables).await?;
@@ -62,10 +75,15 @@ impl GraphQLFetcher {
     let proposals = response
       .proposals
       .iter()
-      .map(|proposal| Proposal {
-        id: proposal.id.parse::<usize>().unwrap(),
-        title: proposal.title.clone(),
-        proposer: proposal.proposer.id.clone(),
+      .map(|proposal| {
+        (
+          Proposal {
+            id: proposal.id.parse::<usize>().unwrap(),
+            title: proposal.title.clone(),
+            proposer: proposal.proposer.id.clone(),
+          },
+          proposal.description.clone(),
+        )
       })
       .collect();
 
@@ -8,21 +8,21 @@ use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  lil_nouns::{handler::Handler, Proposal, Vote},
+  lil_nouns::{handler::Handler, url::UrlBuilder, Proposal, Vote},
   utils::{ens::get_domain_name, get_explorer_address, get_short_address},
 };
 
 pub(crate) struct DiscordHandler {
-  base_url: String,
+  urls: UrlBuilder,
   webhook_url: String,
   cache: Cache,
   client: Client,
 }
 
 impl DiscordHandler {
-  pub fn new(base_url: String, webhook_url: String, cache: Cache, client: Client) -> Self {
+  pub fn new(urls: UrlBuilder, webhook_url: String, cache: Cache, client: Client) -> Self {
     Self {
-      base_url,
+      urls,
       webhook_url,
       cache,
       client,
@@ -30,13 +30,13 @@ impl DiscordHandler {
   }
 
   pub fn new_from_env(env: &Env) -> Result<DiscordHandler> {
-    let base_url = env.var("LIL_NOUNS_BASE_URL")?.to_string();
+    let urls = UrlBuilder::new_from_env(env)?;
     let webhook_url = env.secret("LIL_NOUNS_DISCORD_WEBHOOK_URL")?.to_string();
 
     let cache = Cache::new_from_env(env);
     let client = Client::new();
 
-    Ok(Self::new(base_url, webhook_url, cache, client))
+    Ok(Self::new(urls, webhook_url, cache, client))
   }
 
   async fn execute_webhook(&self, embed: Value) -> Result<()> {
@@ -67,7 +67,7 @@ impl Handler for DiscordHandler {
   async fn handle_new_proposal(&self, proposal: &Proposal) -> Result<()> {
     info!("Handling new proposal: {}", proposal.title);
 
-    let url = format!("{}/{}", self.base_url, proposal.id);
+    let url = self.urls.proposal_url(proposal.id);
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
     let wallet = get_domain_name(&proposal.proposer)
       .await
@@ -110,7 +110,7 @@ impl Handler for DiscordHandler {
       .unwrap()
       .clone();
 
-    let url = format!("{}/{}", self.base_url, proposal.id);
+    let url = self.urls.proposal_url(proposal.id);
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
     let wallet = get_domain_name(&vote.voter)
       .await
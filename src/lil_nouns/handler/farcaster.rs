@@ -12,12 +12,12 @@ use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  lil_nouns::{handler::Handler, Proposal, Vote},
+  lil_nouns::{handler::Handler, url::UrlBuilder, Proposal, Vote},
   utils::{fname::get_username_by_address, link::Link},
 };
 
 pub(crate) struct FarcasterHandler {
-  base_url: String,
+  urls: UrlBuilder,
   warpcast_url: String,
   warpcast_bearer_token: String,
   warpcast_channel_key: String,
@@ -29,7 +29,7 @@ pub(crate) struct FarcasterHandler {
 
 impl FarcasterHandler {
   pub fn new(
-    base_url: String,
+    urls: UrlBuilder,
     warpcast_url: String,
     warpcast_bearer_token: String,
     warpcast_channel_key: String,
@@ -39,7 +39,7 @@ impl FarcasterHandler {
     link: Link,
   ) -> Self {
     Self {
-      base_url,
+      urls,
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
@@ -51,7 +51,7 @@ impl FarcasterHandler {
   }
 
   pub fn new_from_env(env: &Env) -> Result<FarcasterHandler> {
-    let base_url = env.var("LIL_NOUNS_BASE_URL")?.to_string();
+    let urls = UrlBuilder::new_from_env(env)?;
     let warpcast_url = env.var("WARPCAST_API_BASE_URL")?.to_string();
     let warpcast_bearer_token = env.secret("LIL_NOUNS_WARPCAST_TOKEN")?.to_string();
     let warpcast_channel_key = env.var("LIL_NOUNS_WARPCAST_CHANNEL")?.to_string();
@@ -62,7 +62,7 @@ impl FarcasterHandler {
     let link = Link::new_from_env(&env);
 
     Ok(Self::new(
-      base_url,
+      urls,
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
@@ -111,9 +111,9 @@ impl Handler for FarcasterHandler {
 
     let url = &self
       .link
-      .generate(format!("{}/{}", self.base_url, proposal.id))
+      .generate(self.urls.proposal_url(proposal.id))
       .await
-      .unwrap_or_else(|_| format!("{}/{}", self.base_url, proposal.id));
+      .unwrap_or_else(|_| self.urls.proposal_url(proposal.id));
 
     let wallet = get_username_by_address(self.farquest_api_key.as_str(), &proposal.proposer).await;
 
@@ -8,8 +8,9 @@ use crate::{
   lil_nouns::{fetcher::GraphQLFetcher, handler::Handler},
 };
 
-mod fetcher;
+pub(crate) mod fetcher;
 mod handler;
+pub(crate) mod url;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Proposal {
@@ -0,0 +1,38 @@
+use worker::{Env, Result};
+
+pub struct UrlBuilder {
+  base_url: String,
+}
+
+impl UrlBuilder {
+  pub fn new(base_url: String) -> Self {
+    Self {
+      base_url: base_url.trim_end_matches('/').to_string(),
+    }
+  }
+
+  pub fn new_from_env(env: &Env) -> Result<UrlBuilder> {
+    let base_url = env.var("LIL_NOUNS_BASE_URL")?.to_string();
+
+    Ok(Self::new(base_url))
+  }
+
+  pub fn proposal_url(&self, proposal_id: usize) -> String {
+    format!("{}/{}", self.base_url, proposal_id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn builds_proposal_urls() {
+    let builder = UrlBuilder::new("https://lilnouns.camp/proposals/".to_string());
+
+    assert_eq!(
+      builder.proposal_url(123),
+      "https://lilnouns.camp/proposals/123"
+    );
+  }
+}
@@ -1,14 +1,18 @@
-use chrono::{Duration, Utc};
+use std::collections::HashSet;
+
 use graphql_client::{reqwest::post_graphql, GraphQLQuery};
-use log::{debug, error};
+use log::{debug, error, warn};
 use reqwest::Client;
 use serde_json::Value;
 use worker::{Env, Result};
 
-use crate::meta_gov::{Proposal, Vote};
+use crate::meta_gov::{Proposal, Space, Vote};
 
 type Any = Value;
 
+const VOTES_PAGE_SIZE: i64 = 1000;
+const VOTES_MAX_SKIP: i64 = 5000;
+
 #[derive(GraphQLQuery)]
 #[graphql(
   schema_path = "graphql/schemas/snapshot_schema.graphql",
@@ -42,11 +46,10 @@ impl GraphQLFetcher {
     }
   }
 
-  pub fn new_from_env(env: &Env) -> Result<GraphQLFetcher> {
+  pub fn new_from_env(env: &Env, space: &Space) -> Result<GraphQLFetcher> {
     let graphql_url = env.var("META_GOV_SNAPSHOT_GRAPHQL_URL")?.to_string();
-    let space_id = env.var("META_GOV_SNAPSHOT_SPACE_ID")?.to_string();
 
-    Ok(Self::new(graphql_url, space_id))
+    Ok(Self::new(graphql_url, space.id.clone()))
   }
 
   async fn fetch<QueryType: GraphQLQuery>(
@@ -86,37 +89,99 @@ impl GraphQLFetcher {
       .map(|proposal| Proposal {
         id: proposal.id.to_string(),
         title: proposal.title.to_string(),
-        body: proposal.body.clone().unwrap(),
+        body: proposal.body.clone().unwrap_or_default(),
+        author: proposal.author.clone(),
+        state: proposal.state.clone().unwrap_or_default(),
+        start: proposal.start,
+        end: proposal.end,
+        kind: proposal.type_.clone().unwrap_or_default(),
+        choices: proposal.choices.iter().flatten().cloned().collect(),
+        scores: proposal
+          .scores
+          .iter()
+          .flatten()
+          .map(|score| score.unwrap_or_default())
+          .collect(),
+        scores_state: proposal.scores_state.clone().unwrap_or_default(),
+        scores_total: proposal.scores_total.unwrap_or_default(),
+        votes: proposal.votes.unwrap_or_default(),
       })
       .collect();
 
     Some(proposals)
   }
 
-  pub async fn fetch_votes(&self) -> Option<Vec<Vote>> {
-    let now = Utc::now();
-    let thirty_days_ago = now - Duration::try_days(30).unwrap();
-
-    let variables = vote_query::Variables {
-      space: Some(self.space_id.clone()),
-      created_gt: thirty_days_ago.timestamp().try_into().unwrap(),
-    };
-
-    let response = self.fetch::<VoteQuery>(variables).await?;
-
-    let votes = response
-      .votes
-      .as_ref()?
-      .iter()
-      .filter_map(|vote_option| vote_option.as_ref())
-      .map(|vote| Vote {
-        id: vote.id.to_string(),
-        voter: vote.voter.to_string(),
-        reason: vote.clone().reason,
-        choice: vote.choice.as_i64().unwrap_or(0) as isize,
-        proposal_id: vote.proposal.clone().unwrap().id,
-      })
-      .collect();
+  /// Fetches the votes created at or after the given timestamp, oldest first.
+  /// A failing page ends the walk early with the votes collected so far.
+  pub async fn fetch_votes(&self, created_gte: i64) -> Option<Vec<Vote>> {
+    let mut votes: Vec<Vote> = Vec::new();
+    let mut vote_ids = HashSet::new();
+    let mut created_gte = created_gte;
+    let mut skip = 0;
+
+    loop {
+      let variables = vote_query::Variables {
+        space: Some(self.space_id.clone()),
+        created_gte: Some(created_gte),
+        first: VOTES_PAGE_SIZE,
+        skip,
+      };
+
+      let Some(response) = self.fetch::<VoteQuery>(variables).await else {
+        if votes.is_empty() {
+          return None;
+        }
+        warn!("Stopped fetching votes after {:?} votes.", votes.len());
+        break;
+      };
+
+      let page: Vec<Vote> = response
+        .votes
+        .as_ref()?
+        .iter()
+        .filter_map(|vote_option| vote_option.as_ref())
+        .map(|vote| Vote {
+          id: vote.id.to_string(),
+          voter: vote.voter.to_string(),
+          reason: vote.clone().reason,
+          choice: vote.choice.clone(),
+          vp: vote.vp.unwrap_or_default(),
+          created: vote.created,
+          proposal_id: vote.proposal.clone().unwrap().id,
+        })
+        .collect();
+
+      let page_size = page.len() as i64;
+      let last_created = page.last().map(|vote| vote.created);
+      debug!("Fetched a page of {:?} votes (skip {:?}).", page_size, skip);
+
+      votes.extend(
+        page
+          .into_iter()
+          .filter(|vote| vote_ids.insert(vote.id.clone())),
+      );
+
+      if page_size < VOTES_PAGE_SIZE {
+        break;
+      }
+
+      skip += VOTES_PAGE_SIZE;
+
+      // Snapshot rejects large offsets, so continue from the last seen
+      // timestamp instead, re-reading that second and dropping duplicates.
+      // A second holding more votes than that can never be paged past, so
+      // the rest of it is skipped.
+      if skip > VOTES_MAX_SKIP {
+        created_gte = match last_created {
+          Some(created) if created > created_gte => created,
+          _ => {
+            warn!("Skipping the remaining votes created at {:?}.", created_gte);
+            created_gte + 1
+          }
+        };
+        skip = 0;
+      }
+    }
 
     Some(votes)
   }
@@ -8,35 +8,42 @@ use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  meta_gov::{handler::Handler, Proposal, Vote},
+  meta_gov::{
+    format_voting_power,
+    handler::Handler,
+    NounsProposal,
+    NounsVote,
+    Proposal,
+    Space,
+    Vote,
+  },
   utils::{ens::get_domain_name, get_explorer_address, get_short_address},
 };
 
 pub struct DiscordHandler {
-  pub base_url: String,
+  pub space: Space,
   pub webhook_url: String,
   pub cache: Cache,
   pub client: Client,
 }
 
 impl DiscordHandler {
-  pub fn new(base_url: String, webhook_url: String, cache: Cache, client: Client) -> Self {
+  pub fn new(space: Space, webhook_url: String, cache: Cache, client: Client) -> Self {
     Self {
-      base_url,
+      space,
       webhook_url,
       cache,
       client,
     }
   }
 
-  pub fn new_from_env(env: &Env) -> Result<DiscordHandler> {
-    let base_url = env.var("META_GOV_BASE_URL")?.to_string();
-    let webhook_url = env.secret("META_GOV_DISCORD_WEBHOOK_URL")?.to_string();
+  pub fn new_from_env(env: &Env, space: &Space) -> Result<DiscordHandler> {
+    let webhook_url = env.secret(&space.discord_webhook_secret)?.to_string();
 
     let cache = Cache::new_from_env(env);
     let client = Client::new();
 
-    Ok(Self::new(base_url, webhook_url, cache, client))
+    Ok(Self::new(space.clone(), webhook_url, cache, client))
   }
 
   async fn execute_webhook(&self, embed: Value) -> Result<()> {
@@ -61,10 +68,14 @@ impl DiscordHandler {
     Ok(())
   }
 
-  async fn extract_proposal_info(&self, proposal: Proposal) -> Result<(u32, String)> {
+  async fn extract_proposal_info(&self, proposal: Proposal) -> Result<(String, String)> {
+    if !self.space.nouns_mirror {
+      return Ok((proposal.id, proposal.title));
+    }
+
     let captures = Regex::new(r"(\d+): (.+)")
       .unwrap()
-      .captures(&*proposal.title)
+      .captures(&proposal.title)
       .ok_or(Error::from("Capture Failed"))?;
     let proposal_id = captures
       .get(1)
@@ -78,30 +89,43 @@ impl DiscordHandler {
       .map_err(|_| Error::from("Failed to parse proposal ID"))?;
     let proposal_title = proposal_title.as_str().to_string();
 
-    Ok((proposal_id, proposal_title))
+    Ok((proposal_id.to_string(), proposal_title))
   }
 }
 
 #[async_trait(? Send)]
 impl Handler for DiscordHandler {
-  async fn handle_new_proposal(&self, proposal: &Proposal) -> Result<()> {
+  async fn handle_new_proposal(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: Option<&NounsProposal>,
+  ) -> Result<()> {
     match self.extract_proposal_info(proposal.clone()).await {
       Ok((proposal_id, proposal_title)) => {
         info!("Handling new proposal: {}", proposal_title);
 
-        let url = format!("{}/{}", self.base_url, proposal_id);
+        let url = self.space.proposal_url(&proposal_id);
         let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
         let description = format!(
-          "A new Meta Gov proposal has been created: “{}”",
-          proposal_title
+          "A new {} proposal has been created: “{}”",
+          self.space.name, proposal_title
         );
 
+        let mut fields = vec![
+          json!({"name": "Voting Starts", "value": format!("<t:{}:R>", proposal.start), "inline": true}),
+          json!({"name": "Voting Ends", "value": format!("<t:{}:R>", proposal.end), "inline": true}),
+        ];
+        if let Some(nouns_proposal) = nouns_proposal {
+          fields.push(json!({"name": "Nouns DAO", "value": nouns_proposal.format_summary()}));
+        }
+
         let embed = json!({
-            "title": "New Meta Gov Proposal",
+            "title": format!("New {} Proposal", self.space.name),
             "description": description,
             "url": url,
             "color": 0xE40536,
-            "footer": {"text": date}
+            "footer": {"text": date},
+            "fields": fields
         });
 
         self.execute_webhook(embed).await?;
@@ -114,46 +138,129 @@ impl Handler for DiscordHandler {
     Ok(())
   }
 
+  async fn handle_closed_proposal(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: Option<&NounsProposal>,
+  ) -> Result<()> {
+    let (proposal_id, proposal_title) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling closed proposal: {}", proposal_title);
+
+    let url = self.space.proposal_url(&proposal_id);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let outcome = match proposal.winning_choice() {
+      Some(choice) => format!("The winning choice is **{}**.", choice),
+      None => "No single choice won the vote.".to_string(),
+    };
+    let mut description = format!(
+      "Voting on the {} proposal “{}” has closed. {}\n\n{}",
+      self.space.name,
+      proposal_title,
+      outcome,
+      proposal.format_scores()
+    );
+    if let Some(nouns_proposal) = nouns_proposal {
+      description = format!(
+        "{}\n\n**Nouns DAO:** {}",
+        description,
+        nouns_proposal.format_summary()
+      );
+    }
+
+    let embed = json!({
+        "title": format!("{} Proposal Closed", self.space.name),
+        "description": description,
+        "url": url,
+        "color": 0xE40536,
+        "footer": {"text": date}
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
+  async fn handle_closing_proposal(&self, proposal: &Proposal, hours: i64) -> Result<()> {
+    let (proposal_id, proposal_title) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling closing proposal: {}", proposal_title);
+
+    let url = self.space.proposal_url(&proposal_id);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let description = format!(
+      "Voting on the {} proposal “{}” closes <t:{}:R>. {} unique voters have taken part so \
+       far.\n\n{}",
+      self.space.name,
+      proposal_title,
+      proposal.end,
+      proposal.votes,
+      proposal.format_scores()
+    );
+
+    let embed = json!({
+        "title": format!("{} Proposal Closing Within {}h", self.space.name, hours),
+        "description": description,
+        "url": url,
+        "color": 0xE40536,
+        "footer": {"text": date}
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
   async fn handle_new_vote(&self, vote: &Vote) -> Result<()> {
     info!("Handling new vote from address: {}", vote.voter);
 
     let proposals = self
       .cache
-      .get::<Vec<Proposal>>("meta_gov:proposals")
+      .get::<Vec<Proposal>>(&self.space.cache_key("proposals"))
       .await?
       .unwrap_or_default();
 
     let proposal = proposals
       .iter()
       .find(|&a| a.id == vote.proposal_id)
-      .clone()
       .ok_or("Proposal not found in the funding list.")?;
 
     match self.extract_proposal_info(proposal.clone()).await {
       Ok((proposal_id, proposal_title)) => {
         info!("Handling new proposal: {}", proposal_title);
 
-        let url = format!("{}/{}", self.base_url, proposal_id);
+        let url = self.space.proposal_url(&proposal_id);
         let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
         let wallet = get_domain_name(&vote.voter)
           .await
           .unwrap_or(get_short_address(&vote.voter));
+        let voter = format!("{} ({} VP)", wallet, format_voting_power(vote.vp));
 
-        let description = format!(
-          "{} has voted {} “{}” proposal.",
-          wallet,
-          match vote.choice {
-            1 => "for",
-            2 => "against",
-            3 => "abstain on",
-            _ => "unknown",
-          },
-          proposal_title
-        );
+        let description = match proposal.kind.as_str() {
+          "basic" | "" => format!(
+            "{} has voted {} “{}” proposal.",
+            voter,
+            match vote.choice.as_i64() {
+              Some(1) => "for",
+              Some(2) => "against",
+              Some(3) => "abstain on",
+              _ => "unknown",
+            },
+            proposal_title
+          ),
+          _ => format!(
+            "{} has voted on “{}” proposal: {}.",
+            voter,
+            proposal_title,
+            proposal
+              .describe_choice(&vote.choice)
+              .unwrap_or_else(|| "unknown".to_string())
+          ),
+        };
         let explorer = get_explorer_address(&vote.voter);
 
         let embed = json!({
-            "title": "New Meta Gov Proposal Vote",
+            "title": format!("New {} Proposal Vote", self.space.name),
             "description": description,
             "url": url,
             "color": 0xE40536,
@@ -172,4 +279,127 @@ impl Handler for DiscordHandler {
     }
     Ok(())
   }
+
+  async fn handle_batched_votes(&self, proposal_id: &str, votes: &[Vote]) -> Result<()> {
+    info!("Handling {} batched votes on: {}", votes.len(), proposal_id);
+
+    let proposals = self
+      .cache
+      .get::<Vec<Proposal>>(&self.space.cache_key("proposals"))
+      .await?
+      .unwrap_or_default();
+
+    let proposal = proposals
+      .iter()
+      .find(|&a| a.id == proposal_id)
+      .ok_or("Proposal not found in the cached Snapshot proposals.")?;
+
+    let (proposal_id, proposal_title) = self.extract_proposal_info(proposal.clone()).await?;
+
+    let url = self.space.proposal_url(&proposal_id);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let total_vp: f64 = votes.iter().map(|vote| vote.vp).sum();
+
+    let mut description = format!(
+      "{} smaller votes with {} VP in total were cast on “{}” proposal.",
+      votes.len(),
+      format_voting_power(total_vp),
+      proposal_title
+    );
+    if let Some(tally) = proposal.tally_choices(votes) {
+      description = format!("{}\n\n{}", description, tally);
+    }
+
+    let embed = json!({
+        "title": format!("New {} Proposal Votes", self.space.name),
+        "description": description,
+        "url": url,
+        "color": 0xE40536,
+        "footer": {"text": date}
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
+  async fn handle_nouns_vote(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: &NounsProposal,
+    vote: &NounsVote,
+  ) -> Result<()> {
+    let (proposal_id, proposal_title) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling Nouns vote on: {}", proposal_title);
+
+    let url = self.space.proposal_url(&proposal_id);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let outcome = match (proposal.matches_nouns_vote(vote), proposal.winning_choice()) {
+      (Some(true), _) => "This matches the Snapshot result.".to_string(),
+      (Some(false), Some(choice)) => format!(
+        "This does not match the Snapshot result, which was **{}**.",
+        choice
+      ),
+      _ => "The Snapshot vote had no clear result to compare.".to_string(),
+    };
+    let description = format!(
+      "Lil Nouns has voted {} “{}” proposal on Nouns DAO with {} votes. {}\n\n{}",
+      vote.direction(),
+      proposal_title,
+      vote.votes,
+      outcome,
+      nouns_proposal.format_summary()
+    );
+    let explorer = get_explorer_address(&vote.voter);
+
+    let embed = json!({
+        "title": match proposal.matches_nouns_vote(vote) {
+            Some(false) => format!("{} Vote Mismatch on Nouns DAO", self.space.name),
+            _ => format!("{} Vote Cast on Nouns DAO", self.space.name),
+        },
+        "description": description,
+        "url": url,
+        "color": 0xE40536,
+        "footer": {"text": date},
+        "author": {
+            "name": get_short_address(&vote.voter),
+            "url": explorer,
+        }
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
+  async fn handle_missed_nouns_vote(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: &NounsProposal,
+  ) -> Result<()> {
+    let (proposal_id, proposal_title) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling missed Nouns vote on: {}", proposal_title);
+
+    let url = self.space.proposal_url(&proposal_id);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let description = format!(
+      "Lil Nouns did not vote on “{}” proposal before voting ended on Nouns DAO.\n\n{}",
+      proposal_title,
+      nouns_proposal.format_summary()
+    );
+
+    let embed = json!({
+        "title": format!("{} Vote Missed on Nouns DAO", self.space.name),
+        "description": description,
+        "url": url,
+        "color": 0xE40536,
+        "footer": {"text": date}
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
 }
@@ -14,15 +14,22 @@ use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  meta_gov::{handler::Handler, Proposal, Vote},
+  meta_gov::{
+    format_voting_power,
+    handler::Handler,
+    NounsProposal,
+    NounsVote,
+    Proposal,
+    Space,
+    Vote,
+  },
   utils::{ethereum::get_transaction_signer, fname::get_username_by_address, link::Link},
 };
 
 pub(crate) struct FarcasterHandler {
-  base_url: String,
+  space: Space,
   warpcast_url: String,
   warpcast_bearer_token: String,
-  warpcast_channel_key: String,
   farquest_api_key: String,
   cache: Cache,
   client: Client,
@@ -31,20 +38,18 @@ pub(crate) struct FarcasterHandler {
 
 impl FarcasterHandler {
   pub fn new(
-    base_url: String,
+    space: Space,
     warpcast_url: String,
     warpcast_bearer_token: String,
-    warpcast_channel_key: String,
     farquest_api_key: String,
     cache: Cache,
     client: Client,
     link: Link,
   ) -> Self {
     Self {
-      base_url,
+      space,
       warpcast_url,
       warpcast_bearer_token,
-      warpcast_channel_key,
       farquest_api_key,
       cache,
       client,
@@ -52,22 +57,19 @@ impl FarcasterHandler {
     }
   }
 
-  pub fn new_from_env(env: &Env) -> Result<FarcasterHandler> {
-    let base_url = env.var("META_GOV_BASE_URL")?.to_string();
+  pub fn new_from_env(env: &Env, space: &Space) -> Result<FarcasterHandler> {
     let warpcast_url = env.var("WARPCAST_API_BASE_URL")?.to_string();
     let warpcast_bearer_token = env.secret("META_GOV_WARPCAST_TOKEN")?.to_string();
-    let warpcast_channel_key = env.var("META_GOV_WARPCAST_CHANNEL")?.to_string();
     let farquest_api_key = env.secret("FARQUEST_API_KEY")?.to_string();
 
     let cache = Cache::new_from_env(env);
     let client = Client::new();
-    let link = Link::new_from_env(&env);
+    let link = Link::new_from_env(env);
 
     Ok(Self::new(
-      base_url,
+      space.clone(),
       warpcast_url,
       warpcast_bearer_token,
-      warpcast_channel_key,
       farquest_api_key,
       cache,
       client,
@@ -105,7 +107,14 @@ impl FarcasterHandler {
     Ok(response)
   }
 
-  async fn extract_proposal_info(&self, proposal: Proposal) -> Result<(String, String, String)> {
+  async fn extract_proposal_info(
+    &self,
+    proposal: Proposal,
+  ) -> Result<(String, String, Option<String>)> {
+    if !self.space.nouns_mirror {
+      return Ok((proposal.id, proposal.title, None));
+    }
+
     // Extract proposal ID and title
     let captures = Regex::new(r"(\d+): (.+)")
       .unwrap()
@@ -136,13 +145,17 @@ impl FarcasterHandler {
       .as_str()
       .to_string();
 
-    Ok((proposal_id, proposal_title, proposal_hash))
+    Ok((proposal_id, proposal_title, Some(proposal_hash)))
   }
 }
 
 #[async_trait(? Send)]
 impl Handler for FarcasterHandler {
-  async fn handle_new_proposal(&self, proposal: &Proposal) -> Result<()> {
+  async fn handle_new_proposal(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: Option<&NounsProposal>,
+  ) -> Result<()> {
     let (proposal_id, proposal_title, proposal_hash) =
       self.extract_proposal_info(proposal.clone()).await?;
 
@@ -150,34 +163,51 @@ impl Handler for FarcasterHandler {
 
     let url = &self
       .link
-      .generate(format!("{}/{}", self.base_url, proposal_id))
+      .generate(self.space.proposal_url(&proposal_id))
       .await
-      .unwrap_or_else(|_| format!("{}/{}", self.base_url, proposal_id));
-
-    let signer = get_transaction_signer(proposal_hash.as_str())
-      .await
-      .map(|address| address.encode_hex_with_prefix())
-      .unwrap_or_else(|_| "Someone".to_string());
-
-    let wallet = match signer.as_str() {
-      "Someone" => "Someone".to_string(),
-      _ => get_username_by_address(self.farquest_api_key.as_str(), &signer).await,
+      .unwrap_or_else(|_| self.space.proposal_url(&proposal_id));
+
+    let mut description = match proposal_hash {
+      Some(proposal_hash) => {
+        let signer = get_transaction_signer(proposal_hash.as_str())
+          .await
+          .map(|address| address.encode_hex_with_prefix())
+          .unwrap_or_else(|_| "Someone".to_string());
+
+        let wallet = match signer.as_str() {
+          "Someone" => "Someone".to_string(),
+          _ => get_username_by_address(self.farquest_api_key.as_str(), &signer).await,
+        };
+
+        format!(
+          "{} created a new proposal on Nouns: “{}”",
+          wallet, proposal_title
+        )
+      }
+      None => {
+        let wallet =
+          get_username_by_address(self.farquest_api_key.as_str(), &proposal.author).await;
+
+        format!(
+          "{} created a new proposal on {}: “{}”",
+          wallet, self.space.name, proposal_title
+        )
+      }
     };
 
-    let description = format!(
-      "{} created a new proposal on Nouns: “{}”",
-      wallet, proposal_title
-    );
+    if let Some(nouns_proposal) = nouns_proposal {
+      description = format!("{}\n\n{}", description, nouns_proposal.format_summary());
+    }
 
     let request_data = json!({
         "text": description,
         "embeds": [url],
-        "channelKey": self.warpcast_channel_key
+        "channelKey": self.space.warpcast_channel
     });
 
     let response = self.make_http_request(request_data).await.map_err(|e| {
       error!("Failed to make HTTP request: {}", e);
-      return e;
+      e
     })?;
 
     let response_body = response.text().await.map_err(|e| {
@@ -202,9 +232,9 @@ impl Handler for FarcasterHandler {
 
     let mut proposals_casts = self
       .cache
-      .get::<HashMap<String, String>>("meta_gov:proposals:casts")
+      .get::<HashMap<String, String>>(&self.space.cache_key("proposals:casts"))
       .await?
-      .ok_or("Failed to retrieve proposals casts")?;
+      .unwrap_or_default();
     debug!("Proposals casts before insertion: {:?}", proposals_casts);
 
     proposals_casts.insert(proposal_id, cast_hash.to_string());
@@ -215,33 +245,128 @@ impl Handler for FarcasterHandler {
 
     self
       .cache
-      .put("meta_gov:proposals:casts", &proposals_casts_as_string)
+      .put(
+        &self.space.cache_key("proposals:casts"),
+        &proposals_casts_as_string,
+      )
       .await;
     debug!("Finished putting proposals casts in cache");
 
     Ok(())
   }
 
+  async fn handle_closed_proposal(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: Option<&NounsProposal>,
+  ) -> Result<()> {
+    let (proposal_id, proposal_title, _) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling closed proposal: {}", proposal_title);
+
+    let proposals_casts = self
+      .cache
+      .get::<HashMap<String, String>>(&self.space.cache_key("proposals:casts"))
+      .await?
+      .unwrap_or_default();
+
+    let outcome = match proposal.winning_choice() {
+      Some(choice) => format!("The winning choice is “{}”.", choice),
+      None => "No single choice won the vote.".to_string(),
+    };
+    let mut description = format!(
+      "Voting on “{}” has closed. {}\n\n{}",
+      proposal_title,
+      outcome,
+      proposal.format_scores()
+    );
+    if let Some(nouns_proposal) = nouns_proposal {
+      description = format!(
+        "{}\n\nNouns DAO: {}",
+        description,
+        nouns_proposal.format_summary()
+      );
+    }
+
+    let request_data = match proposals_casts.get(&proposal_id) {
+      Some(cast_hash) => json!({
+        "text": description,
+        "channelKey": self.space.warpcast_channel,
+        "parent": {"hash": cast_hash},
+      }),
+      None => {
+        let url = &self
+          .link
+          .generate(self.space.proposal_url(&proposal_id))
+          .await
+          .unwrap_or_else(|_| self.space.proposal_url(&proposal_id));
+
+        json!({
+          "text": description,
+          "embeds": [url],
+          "channelKey": self.space.warpcast_channel,
+        })
+      }
+    };
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_closing_proposal(&self, proposal: &Proposal, hours: i64) -> Result<()> {
+    let (proposal_id, proposal_title, _) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling closing proposal: {}", proposal_title);
+
+    let proposals_casts = self
+      .cache
+      .get::<HashMap<String, String>>(&self.space.cache_key("proposals:casts"))
+      .await?
+      .ok_or("Failed to retrieve proposals casts")?;
+
+    let cast_hash = proposals_casts
+      .get(&proposal_id)
+      .ok_or("Cast hash not found")?;
+
+    let description = format!(
+      "Voting on “{}” closes within {} hours. {} unique voters have taken part so far.\n\n{}",
+      proposal_title,
+      hours,
+      proposal.votes,
+      proposal.format_scores()
+    );
+
+    let request_data = json!({
+      "text": description,
+      "channelKey": self.space.warpcast_channel,
+      "parent": {"hash": cast_hash},
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
   async fn handle_new_vote(&self, vote: &Vote) -> Result<()> {
     info!("Handling new vote from address: {}", vote.voter);
 
     let proposals = self
       .cache
-      .get::<Vec<Proposal>>("meta_gov:proposals")
+      .get::<Vec<Proposal>>(&self.space.cache_key("proposals"))
       .await?
       .unwrap_or_default();
 
     let proposal = proposals
       .iter()
       .find(|&a| a.id == vote.proposal_id)
-      .clone()
       .ok_or("Proposal not found in the funding list.")?;
 
     let (proposal_id, proposal_title, _) = self.extract_proposal_info(proposal.clone()).await?;
 
     let proposals_casts = self
       .cache
-      .get::<HashMap<String, String>>("meta_gov:proposals:casts")
+      .get::<HashMap<String, String>>(&self.space.cache_key("proposals:casts"))
       .await?
       .ok_or("Failed to retrieve proposals casts")?;
 
@@ -250,18 +375,29 @@ impl Handler for FarcasterHandler {
       .ok_or("Cast hash not found")?;
 
     let wallet = get_username_by_address(self.farquest_api_key.as_str(), &vote.voter).await;
-
-    let mut description = format!(
-      "{} has voted {} “{}” proposal.",
-      wallet,
-      match vote.choice {
-        1 => "for",
-        2 => "against",
-        3 => "abstain on",
-        _ => "unknown",
-      },
-      proposal_title
-    );
+    let voter = format!("{} ({} VP)", wallet, format_voting_power(vote.vp));
+
+    let mut description = match proposal.kind.as_str() {
+      "basic" | "" => format!(
+        "{} has voted {} “{}” proposal.",
+        voter,
+        match vote.choice.as_i64() {
+          Some(1) => "for",
+          Some(2) => "against",
+          Some(3) => "abstain on",
+          _ => "unknown",
+        },
+        proposal_title
+      ),
+      _ => format!(
+        "{} has voted on “{}” proposal: {}.",
+        voter,
+        proposal_title,
+        proposal
+          .describe_choice(&vote.choice)
+          .unwrap_or_else(|| "unknown".to_string())
+      ),
+    };
 
     let chars_limit = 1024 - 10 - description.len();
     let mut vote_reason = vote
@@ -280,7 +416,56 @@ impl Handler for FarcasterHandler {
 
     let request_data = json!({
       "text": description,
-      "channelKey": self.warpcast_channel_key,
+      "channelKey": self.space.warpcast_channel,
+      "parent": {"hash": cast_hash},
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_batched_votes(&self, proposal_id: &str, votes: &[Vote]) -> Result<()> {
+    info!("Handling {} batched votes on: {}", votes.len(), proposal_id);
+
+    let proposals = self
+      .cache
+      .get::<Vec<Proposal>>(&self.space.cache_key("proposals"))
+      .await?
+      .unwrap_or_default();
+
+    let proposal = proposals
+      .iter()
+      .find(|&a| a.id == proposal_id)
+      .ok_or("Proposal not found in the cached Snapshot proposals.")?;
+
+    let (proposal_id, proposal_title, _) = self.extract_proposal_info(proposal.clone()).await?;
+
+    let proposals_casts = self
+      .cache
+      .get::<HashMap<String, String>>(&self.space.cache_key("proposals:casts"))
+      .await?
+      .ok_or("Failed to retrieve proposals casts")?;
+
+    let cast_hash = proposals_casts
+      .get(&proposal_id)
+      .ok_or("Cast hash not found")?;
+
+    let total_vp: f64 = votes.iter().map(|vote| vote.vp).sum();
+
+    let mut description = format!(
+      "{} smaller votes with {} VP in total were cast on “{}” proposal.",
+      votes.len(),
+      format_voting_power(total_vp),
+      proposal_title
+    );
+    if let Some(tally) = proposal.tally_choices(votes) {
+      description = format!("{}\n\n{}", description, tally);
+    }
+
+    let request_data = json!({
+      "text": description,
+      "channelKey": self.space.warpcast_channel,
       "parent": {"hash": cast_hash},
     });
 
@@ -288,4 +473,110 @@ impl Handler for FarcasterHandler {
 
     Ok(())
   }
+
+  async fn handle_nouns_vote(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: &NounsProposal,
+    vote: &NounsVote,
+  ) -> Result<()> {
+    let (proposal_id, proposal_title, _) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling Nouns vote on: {}", proposal_title);
+
+    let proposals_casts = self
+      .cache
+      .get::<HashMap<String, String>>(&self.space.cache_key("proposals:casts"))
+      .await?
+      .unwrap_or_default();
+
+    let outcome = match (proposal.matches_nouns_vote(vote), proposal.winning_choice()) {
+      (Some(true), _) => "This matches the Snapshot result.".to_string(),
+      (Some(false), Some(choice)) => format!(
+        "This does not match the Snapshot result, which was “{}”.",
+        choice
+      ),
+      _ => "The Snapshot vote had no clear result to compare.".to_string(),
+    };
+    let description = format!(
+      "Lil Nouns has voted {} “{}” proposal on Nouns DAO with {} votes. {}\n\n{}",
+      vote.direction(),
+      proposal_title,
+      vote.votes,
+      outcome,
+      nouns_proposal.format_summary()
+    );
+
+    let request_data = match proposals_casts.get(&proposal_id) {
+      Some(cast_hash) => json!({
+        "text": description,
+        "channelKey": self.space.warpcast_channel,
+        "parent": {"hash": cast_hash},
+      }),
+      None => {
+        let url = &self
+          .link
+          .generate(self.space.proposal_url(&proposal_id))
+          .await
+          .unwrap_or_else(|_| self.space.proposal_url(&proposal_id));
+
+        json!({
+          "text": description,
+          "embeds": [url],
+          "channelKey": self.space.warpcast_channel,
+        })
+      }
+    };
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_missed_nouns_vote(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: &NounsProposal,
+  ) -> Result<()> {
+    let (proposal_id, proposal_title, _) = self.extract_proposal_info(proposal.clone()).await?;
+
+    info!("Handling missed Nouns vote on: {}", proposal_title);
+
+    let proposals_casts = self
+      .cache
+      .get::<HashMap<String, String>>(&self.space.cache_key("proposals:casts"))
+      .await?
+      .unwrap_or_default();
+
+    let description = format!(
+      "Lil Nouns did not vote on “{}” proposal before voting ended on Nouns DAO.\n\n{}",
+      proposal_title,
+      nouns_proposal.format_summary()
+    );
+
+    let request_data = match proposals_casts.get(&proposal_id) {
+      Some(cast_hash) => json!({
+        "text": description,
+        "channelKey": self.space.warpcast_channel,
+        "parent": {"hash": cast_hash},
+      }),
+      None => {
+        let url = &self
+          .link
+          .generate(self.space.proposal_url(&proposal_id))
+          .await
+          .unwrap_or_else(|_| self.space.proposal_url(&proposal_id));
+
+        json!({
+          "text": description,
+          "embeds": [url],
+          "channelKey": self.space.warpcast_channel,
+        })
+      }
+    };
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
 }
@@ -1,13 +1,35 @@
 use async_trait::async_trait;
 use worker::Result;
 
-use crate::meta_gov::{Proposal, Vote};
+use crate::meta_gov::{NounsProposal, NounsVote, Proposal, Vote};
 
 pub(crate) mod discord;
 pub(crate) mod farcaster;
 
 #[async_trait(? Send)]
 pub trait Handler {
-  async fn handle_new_proposal(&self, proposal: &Proposal) -> Result<()>;
+  async fn handle_new_proposal(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: Option<&NounsProposal>,
+  ) -> Result<()>;
+  async fn handle_closed_proposal(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: Option<&NounsProposal>,
+  ) -> Result<()>;
+  async fn handle_closing_proposal(&self, proposal: &Proposal, hours: i64) -> Result<()>;
   async fn handle_new_vote(&self, vote: &Vote) -> Result<()>;
+  async fn handle_batched_votes(&self, proposal_id: &str, votes: &[Vote]) -> Result<()>;
+  async fn handle_nouns_vote(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: &NounsProposal,
+    vote: &NounsVote,
+  ) -> Result<()>;
+  async fn handle_missed_nouns_vote(
+    &self,
+    proposal: &Proposal,
+    nouns_proposal: &NounsProposal,
+  ) -> Result<()>;
 }
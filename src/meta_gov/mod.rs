@@ -1,91 +1,702 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
 use handler::{discord::DiscordHandler, farcaster::FarcasterHandler};
 use log::{debug, error, info, warn};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use worker::{Env, Result};
 
 use crate::{
   cache::Cache,
-  meta_gov::{fetcher::GraphQLFetcher, handler::Handler},
+  meta_gov::{fetcher::GraphQLFetcher, handler::Handler, nouns::NounsFetcher},
+  utils::ethereum::get_block_number,
 };
 
 mod fetcher;
 mod handler;
+mod nouns;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Space {
+  pub(crate) id: String,
+  pub(crate) name: String,
+  pub(crate) base_url: String,
+  pub(crate) discord_webhook_secret: String,
+  pub(crate) warpcast_channel: String,
+  #[serde(default)]
+  pub(crate) nouns_mirror: bool,
+  /// Whether the space takes over the unscoped `meta_gov:*` cache keys written
+  /// before several spaces could be watched.
+  #[serde(default)]
+  pub(crate) legacy_cache: bool,
+  /// The address voting on Nouns DAO for the space; vote checks are skipped
+  /// while it is empty.
+  #[serde(default)]
+  pub(crate) nouns_delegate: String,
+}
+
+impl Space {
+  pub fn list_from_env(env: &Env) -> Result<Vec<Space>> {
+    let spaces = env.var("META_GOV_SPACES")?.to_string();
+
+    serde_json::from_str(&spaces).map_err(|e| {
+      error!("Failed to parse META_GOV_SPACES: {}", e);
+      e.into()
+    })
+  }
+
+  pub fn proposal_url(&self, proposal_id: &str) -> String {
+    format!("{}/{}", self.base_url.trim_end_matches('/'), proposal_id)
+  }
+
+  pub fn cache_key(&self, key: &str) -> String {
+    format!("meta_gov:{}:{}", self.id, key)
+  }
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Proposal {
   pub(crate) id: String,
   pub(crate) title: String,
   body: String,
+  #[serde(default)]
+  pub(crate) author: String,
+  #[serde(default)]
+  pub(crate) state: String,
+  #[serde(default)]
+  pub(crate) start: i64,
+  #[serde(default)]
+  pub(crate) end: i64,
+  #[serde(default)]
+  pub(crate) kind: String,
+  #[serde(default)]
+  pub(crate) choices: Vec<String>,
+  #[serde(default)]
+  pub(crate) scores: Vec<f64>,
+  #[serde(default)]
+  pub(crate) scores_state: String,
+  #[serde(default)]
+  pub(crate) scores_total: f64,
+  #[serde(default)]
+  pub(crate) votes: i64,
+}
+
+impl Proposal {
+  /// The Nouns DAO proposal id parsed from a mirrored "123: Title" title.
+  pub fn nouns_proposal_id(&self) -> Option<u32> {
+    Regex::new(r"^(\d+): ")
+      .unwrap()
+      .captures(&self.title)?
+      .get(1)?
+      .as_str()
+      .parse()
+      .ok()
+  }
+
+  /// Whether the proposal is closed and its scores are final.
+  pub fn is_finalized(&self) -> bool {
+    self.state == "closed" && self.scores_state == "final"
+  }
+
+  /// Whether an on-chain Nouns vote follows the Snapshot winning choice.
+  pub fn matches_nouns_vote(&self, vote: &NounsVote) -> Option<bool> {
+    let support = match self.winning_choice()?.to_lowercase().as_str() {
+      "against" => 0,
+      "for" => 1,
+      "abstain" => 2,
+      _ => return None,
+    };
+
+    Some(support == vote.support)
+  }
+
+  /// The top scoring choice, or `None` on a tie or when nobody voted.
+  pub fn winning_choice(&self) -> Option<&str> {
+    if self.scores_total <= 0.0 {
+      return None;
+    }
+
+    let max = self.scores.iter().cloned().fold(f64::MIN, f64::max);
+    let mut leaders = self
+      .scores
+      .iter()
+      .enumerate()
+      .filter(|(_, &score)| score == max);
+
+    match (leaders.next(), leaders.next()) {
+      (Some((index, _)), None) => self.choices.get(index).map(String::as_str),
+      _ => None,
+    }
+  }
+
+  /// Describes a vote `choice` according to the proposal's voting type, e.g.
+  /// "For", "ranked: A > C > B" or "60% For, 40% Abstain".
+  pub fn describe_choice(&self, choice: &Value) -> Option<String> {
+    let choice_name = |index: &Value| {
+      index
+        .as_u64()
+        .or_else(|| index.as_str().and_then(|index| index.parse().ok()))
+        .and_then(|index| self.choices.get((index as usize).checked_sub(1)?))
+        .cloned()
+    };
+
+    match (self.kind.as_str(), choice) {
+      ("approval", Value::Array(indexes)) => {
+        let names = indexes
+          .iter()
+          .map(choice_name)
+          .collect::<Option<Vec<_>>>()?;
+        Some(format!("approval: {}", names.join(", ")))
+      }
+      ("ranked-choice", Value::Array(indexes)) => {
+        let names = indexes
+          .iter()
+          .map(choice_name)
+          .collect::<Option<Vec<_>>>()?;
+        Some(format!("ranked: {}", names.join(" > ")))
+      }
+      ("weighted" | "quadratic", Value::Object(weights)) => {
+        let total: f64 = weights.values().filter_map(Value::as_f64).sum();
+        if total <= 0.0 {
+          return None;
+        }
+
+        let mut shares = weights
+          .iter()
+          .filter_map(|(index, weight)| {
+            let index = index.parse::<usize>().ok()?;
+            let weight = weight.as_f64().filter(|weight| *weight > 0.0)?;
+            Some((index, self.choices.get(index.checked_sub(1)?)?, weight))
+          })
+          .collect::<Vec<_>>();
+        shares.sort_by_key(|(index, ..)| *index);
+
+        let shares = shares
+          .iter()
+          .map(|(_, name, weight)| format!("{:.0}% {}", weight / total * 100.0, name))
+          .collect::<Vec<_>>();
+        Some(shares.join(", "))
+      }
+      (_, Value::Number(_) | Value::String(_)) => choice_name(choice),
+      _ => None,
+    }
+  }
+
+  /// One line per choice with its score and share of the total.
+  pub fn format_scores(&self) -> String {
+    self
+      .choices
+      .iter()
+      .enumerate()
+      .map(|(index, choice)| {
+        let score = self.scores.get(index).cloned().unwrap_or_default();
+        let percent = if self.scores_total > 0.0 {
+          score / self.scores_total * 100.0
+        } else {
+          0.0
+        };
+        format!("{}: {:.0} ({:.1}%)", choice, score, percent)
+      })
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+
+  /// Counts votes per choice, e.g. "3 For, 1 Against", for single choice
+  /// voting types.
+  pub fn tally_choices(&self, votes: &[Vote]) -> Option<String> {
+    if !matches!(self.kind.as_str(), "basic" | "single-choice") {
+      return None;
+    }
+
+    let tally = self
+      .choices
+      .iter()
+      .enumerate()
+      .filter_map(|(index, choice)| {
+        let count = votes
+          .iter()
+          .filter(|vote| vote.choice.as_u64() == Some(index as u64 + 1))
+          .count();
+        (count > 0).then(|| format!("{} {}", count, choice))
+      })
+      .collect::<Vec<_>>();
+
+    (!tally.is_empty()).then(|| tally.join(", "))
+  }
+}
+
+pub fn format_voting_power(vp: f64) -> String {
+  if vp >= 10.0 || vp.fract() == 0.0 {
+    format!("{:.0}", vp)
+  } else {
+    format!("{:.2}", vp)
+  }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Vote {
   pub(crate) id: String,
   pub(crate) voter: String,
-  pub(crate) choice: isize,
+  pub(crate) choice: Value,
   pub(crate) proposal_id: String,
   pub reason: Option<String>,
+  #[serde(default)]
+  pub(crate) vp: f64,
+  #[serde(default)]
+  pub(crate) created: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NounsProposal {
+  pub(crate) id: u32,
+  pub(crate) title: String,
+  pub(crate) status: String,
+  pub(crate) end_block: String,
+  #[serde(default)]
+  pub(crate) objection_period_end_block: String,
+  pub(crate) for_votes: String,
+  pub(crate) against_votes: String,
+  pub(crate) abstain_votes: String,
+  pub(crate) quorum_votes: String,
+  pub(crate) votes: Vec<NounsVote>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NounsVote {
+  pub(crate) id: String,
+  pub(crate) voter: String,
+  pub(crate) support: i64,
+  pub(crate) votes: String,
+  pub(crate) reason: Option<String>,
+  pub(crate) block_number: String,
 }
 
+impl NounsProposal {
+  pub fn format_summary(&self) -> String {
+    let status = match self.voting_end_block() {
+      Some(end_block) if self.status == "ACTIVE" => {
+        format!("{} until block {}", self.status, end_block)
+      }
+      _ => self.status.clone(),
+    };
+
+    format!(
+      "{}\nFor {} · Against {} · Abstain {} · Quorum {}",
+      status, self.for_votes, self.against_votes, self.abstain_votes, self.quorum_votes
+    )
+  }
+
+  pub fn is_withdrawn(&self) -> bool {
+    matches!(self.status.as_str(), "CANCELLED" | "VETOED")
+  }
+
+  /// The last block votes can be cast in, including any objection period.
+  pub fn voting_end_block(&self) -> Option<u64> {
+    let end_block = self.end_block.parse::<u64>().ok()?;
+    let objection_period_end_block = self.objection_period_end_block.parse::<u64>().unwrap_or(0);
+
+    Some(end_block.max(objection_period_end_block))
+  }
+
+  pub fn is_voting_ended(&self, block_number: u64) -> bool {
+    match self.status.as_str() {
+      "QUEUED" | "EXECUTED" | "CANCELLED" | "VETOED" => true,
+      _ => self
+        .voting_end_block()
+        .is_some_and(|end_block| block_number > end_block),
+    }
+  }
+
+  pub fn vote_by(&self, voter: &str) -> Option<&NounsVote> {
+    self
+      .votes
+      .iter()
+      .find(|vote| vote.voter.eq_ignore_ascii_case(voter))
+  }
+}
+
+impl NounsVote {
+  pub fn direction(&self) -> &str {
+    match self.support {
+      0 => "against",
+      1 => "for",
+      2 => "abstain on",
+      _ => "unknown",
+    }
+  }
+}
+
+/// How far back the single-space deployment looked for votes.
+const LEGACY_VOTE_WINDOW: i64 = 30 * 24 * 60 * 60;
+
 pub struct MetaGov {
+  space: Space,
   cache: Cache,
   fetcher: GraphQLFetcher,
+  nouns_fetcher: NounsFetcher,
   handlers: Vec<Box<dyn Handler>>,
+  min_voting_power: f64,
+  batch_small_votes: bool,
+  reminder_hours: Vec<i64>,
 }
 
 impl MetaGov {
-  pub fn new(cache: Cache, fetcher: GraphQLFetcher, handlers: Vec<Box<dyn Handler>>) -> Self {
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    space: Space,
+    cache: Cache,
+    fetcher: GraphQLFetcher,
+    nouns_fetcher: NounsFetcher,
+    handlers: Vec<Box<dyn Handler>>,
+    min_voting_power: f64,
+    batch_small_votes: bool,
+    reminder_hours: Vec<i64>,
+  ) -> Self {
     Self {
+      space,
       cache,
       fetcher,
+      nouns_fetcher,
       handlers,
+      min_voting_power,
+      batch_small_votes,
+      reminder_hours,
     }
   }
 
-  pub fn new_from_env(env: &Env) -> Result<Self> {
+  pub fn new_from_env(env: &Env, space: &Space) -> Result<Self> {
     let cache = Cache::new_from_env(env);
-    let fetcher = GraphQLFetcher::new_from_env(env)?;
+    let fetcher = GraphQLFetcher::new_from_env(env, space)?;
+    let nouns_fetcher = NounsFetcher::new_from_env(env)?;
     let mut handlers = vec![];
 
     if env.var("META_GOV_DISCORD_ENABLED")?.to_string() == "true" {
-      let discord_handler: Box<dyn Handler> = Box::new(DiscordHandler::new_from_env(env)?);
+      let discord_handler: Box<dyn Handler> = Box::new(DiscordHandler::new_from_env(env, space)?);
       handlers.push(discord_handler);
     }
 
     if env.var("META_GOV_FARCASTER_ENABLED")?.to_string() == "true" {
-      let farcaster_handler: Box<dyn Handler> = Box::new(FarcasterHandler::new_from_env(env)?);
+      let farcaster_handler: Box<dyn Handler> =
+        Box::new(FarcasterHandler::new_from_env(env, space)?);
       handlers.push(farcaster_handler);
     }
 
-    Ok(Self::new(cache, fetcher, handlers))
+    let min_voting_power = env
+      .var("META_GOV_MIN_VOTING_POWER")?
+      .to_string()
+      .parse::<f64>()
+      .unwrap_or_default();
+    let batch_small_votes = env.var("META_GOV_BATCH_SMALL_VOTES")?.to_string() == "true";
+    let reminder_hours = env
+      .var("META_GOV_REMINDER_HOURS")?
+      .to_string()
+      .split(',')
+      .filter_map(|hours| hours.trim().parse::<i64>().ok())
+      .collect();
+
+    Ok(Self::new(
+      space.clone(),
+      cache,
+      fetcher,
+      nouns_fetcher,
+      handlers,
+      min_voting_power,
+      batch_small_votes,
+      reminder_hours,
+    ))
+  }
+
+  async fn fetch_nouns_proposal(&self, proposal: &Proposal) -> Option<NounsProposal> {
+    if !self.space.nouns_mirror {
+      return None;
+    }
+
+    let nouns_proposal = self
+      .nouns_fetcher
+      .fetch_proposal(proposal.nouns_proposal_id()?, &self.space.nouns_delegate)
+      .await;
+    if nouns_proposal.is_none() {
+      warn!("Failed to fetch Nouns proposal for {:?}", proposal.id);
+    }
+
+    nouns_proposal
+  }
+
+  /// Copies the unscoped cache entries of a single-space deployment to the
+  /// space's keys, so cast threads and closed proposals carry over. Its seen
+  /// votes seed the votes cursor over the window it used to look back on, so
+  /// votes cast since its last run are still announced.
+  async fn migrate_legacy_cache(&self) {
+    for key in ["proposals", "proposals:closed", "proposals:casts"] {
+      let legacy_key = format!("meta_gov:{}", key);
+      let space_key = self.space.cache_key(key);
+
+      if self.cache.has(&space_key).await || !self.cache.has(&legacy_key).await {
+        continue;
+      }
+
+      match self.cache.get::<Value>(&legacy_key).await {
+        Ok(Some(value)) => {
+          info!("Migrating {} to {}.", legacy_key, space_key);
+          self.cache.put(&space_key, &value).await;
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to read {}: {}", legacy_key, e),
+      }
+    }
+
+    if self.cache.has(&self.space.cache_key("votes:cursor")).await {
+      return;
+    }
+
+    match self.cache.get::<Vec<Value>>("meta_gov:votes").await {
+      Ok(Some(votes)) => {
+        let cursor = Utc::now().timestamp() - LEGACY_VOTE_WINDOW;
+        let cursor_ids: Vec<String> = votes
+          .iter()
+          .filter_map(|vote| vote["id"].as_str().map(str::to_string))
+          .collect();
+        info!(
+          "Migrating {:?} seen votes from meta_gov:votes.",
+          cursor_ids.len()
+        );
+        self
+          .cache
+          .put(&self.space.cache_key("votes:cursor"), &cursor)
+          .await;
+        self
+          .cache
+          .put(&self.space.cache_key("votes:cursor_ids"), &cursor_ids)
+          .await;
+      }
+      Ok(None) => {}
+      Err(e) => error!("Failed to read meta_gov:votes: {}", e),
+    }
   }
 
   pub async fn setup(&self) {
     debug!("Setup function started.");
 
-    if !self.cache.has("meta_gov:proposals").await {
+    if self.space.legacy_cache {
+      self.migrate_legacy_cache().await;
+    }
+
+    if !self.cache.has(&self.space.cache_key("proposals")).await {
       if let Some(proposals) = self.fetcher.fetch_proposals().await {
         info!("Fetched {:?} proposals.", proposals.len());
         debug!("Putting fetched proposals into cache.");
-        self.cache.put("meta_gov:proposals", &proposals).await;
+        self
+          .cache
+          .put(&self.space.cache_key("proposals"), &proposals)
+          .await;
       } else {
         warn!("Failed to fetch proposals");
       }
     };
 
-    if !self.cache.has("meta_gov:votes").await {
-      if let Some(votes) = self.fetcher.fetch_votes().await {
-        info!("Fetched {:?} votes.", votes.len());
-        debug!("Putting fetched votes into cache.");
-        self.cache.put("meta_gov:votes", &votes).await;
+    if !self
+      .cache
+      .has(&self.space.cache_key("proposals:closed"))
+      .await
+    {
+      if let Some(proposals) = self.fetcher.fetch_proposals().await {
+        let closed_ids: Vec<_> = proposals
+          .iter()
+          .filter(|proposal| proposal.is_finalized())
+          .map(|proposal| proposal.id.clone())
+          .collect();
+        info!("Found {:?} closed proposals.", closed_ids.len());
+        debug!("Putting closed proposals into cache.");
+        self
+          .cache
+          .put(&self.space.cache_key("proposals:closed"), &closed_ids)
+          .await;
       } else {
-        warn!("Failed to fetch votes");
+        warn!("Failed to fetch proposals");
       }
     };
 
+    if self.space.nouns_mirror
+      && !self
+        .cache
+        .has(&self.space.cache_key("proposals:nouns_votes"))
+        .await
+    {
+      let closed_ids = self
+        .cache
+        .get::<Vec<String>>(&self.space.cache_key("proposals:closed"))
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+      debug!("Putting Nouns votes into cache.");
+      self
+        .cache
+        .put(&self.space.cache_key("proposals:nouns_votes"), &closed_ids)
+        .await;
+    };
+
+    if !self.cache.has(&self.space.cache_key("votes:cursor")).await {
+      let cursor = Utc::now().timestamp();
+      info!("Starting votes cursor at {:?}.", cursor);
+      debug!("Putting votes cursor into cache.");
+      self
+        .cache
+        .put(&self.space.cache_key("votes:cursor"), &cursor)
+        .await;
+    };
+
     debug!("Setup function finished.");
   }
 
+  async fn check_closing_proposals(&self, proposals: &[Proposal]) -> Result<()> {
+    let mut reminders = self
+      .cache
+      .get::<Vec<String>>(&self.space.cache_key("proposals:reminders"))
+      .await?
+      .unwrap_or_default();
+    let reminders_count = reminders.len();
+
+    let active_proposals: Vec<_> = proposals
+      .iter()
+      .filter(|proposal| proposal.state == "active")
+      .collect();
+    reminders.retain(|reminder| {
+      active_proposals
+        .iter()
+        .any(|proposal| reminder.starts_with(&format!("{}:", proposal.id)))
+    });
+
+    let now = Utc::now().timestamp();
+    let mut changed = reminders.len() != reminders_count;
+
+    for proposal in active_proposals {
+      let remaining = proposal.end - now;
+      let due_hours: Vec<_> = self
+        .reminder_hours
+        .iter()
+        .filter(|&&hours| remaining > 0 && remaining <= hours * 60 * 60)
+        .filter(|hours| !reminders.contains(&format!("{}:{}", proposal.id, hours)))
+        .collect();
+
+      let Some(&&hours) = due_hours.iter().min() else {
+        continue;
+      };
+
+      info!("Handling a closing proposal... ({:?})", proposal.id);
+      for handler in &self.handlers {
+        if let Err(err) = handler.handle_closing_proposal(proposal, hours).await {
+          error!("Failed to handle closing proposal: {:?}", err);
+        } else {
+          debug!("Successfully handled closing proposal: {:?}", proposal.id);
+        }
+      }
+
+      for hours in due_hours {
+        reminders.push(format!("{}:{}", proposal.id, hours));
+      }
+      changed = true;
+    }
+
+    if changed {
+      self
+        .cache
+        .put(&self.space.cache_key("proposals:reminders"), &reminders)
+        .await;
+      info!("Updated proposal reminders in cache");
+    }
+
+    Ok(())
+  }
+
+  async fn check_nouns_votes(&self, proposals: &[Proposal]) -> Result<()> {
+    let mut voted_ids = self
+      .cache
+      .get::<Vec<String>>(&self.space.cache_key("proposals:nouns_votes"))
+      .await?
+      .unwrap_or_default();
+
+    // Mirrored proposals stay pending until their Nouns voting has ended,
+    // however long an objection period or queue keeps it open.
+    let pending_proposals: Vec<_> = proposals
+      .iter()
+      .filter(|proposal| {
+        proposal.is_finalized()
+          && proposal.nouns_proposal_id().is_some()
+          && !voted_ids.contains(&proposal.id)
+      })
+      .collect();
+
+    debug!(
+      "Checking {:?} proposals for Nouns votes.",
+      pending_proposals.len()
+    );
+
+    if pending_proposals.is_empty() {
+      return Ok(());
+    }
+
+    let block_number = get_block_number()
+      .await
+      .map_err(|e| warn!("Failed to fetch block number: {}", e))
+      .ok();
+
+    let mut changed = false;
+    for proposal in pending_proposals {
+      let Some(nouns_proposal) = self.fetch_nouns_proposal(proposal).await else {
+        continue;
+      };
+      let Some(vote) = nouns_proposal.vote_by(&self.space.nouns_delegate) else {
+        if !block_number.is_some_and(|number| nouns_proposal.is_voting_ended(number)) {
+          continue;
+        }
+
+        if !nouns_proposal.is_withdrawn() {
+          info!("Handling a missed Nouns vote... ({:?})", proposal.id);
+          for handler in &self.handlers {
+            if let Err(err) = handler
+              .handle_missed_nouns_vote(proposal, &nouns_proposal)
+              .await
+            {
+              error!("Failed to handle missed Nouns vote: {:?}", err);
+            } else {
+              debug!("Successfully handled missed Nouns vote: {:?}", proposal.id);
+            }
+          }
+        }
+        voted_ids.push(proposal.id.clone());
+        changed = true;
+        continue;
+      };
+
+      info!("Handling a Nouns vote... ({:?})", proposal.id);
+      for handler in &self.handlers {
+        if let Err(err) = handler
+          .handle_nouns_vote(proposal, &nouns_proposal, vote)
+          .await
+        {
+          error!("Failed to handle Nouns vote: {:?}", err);
+        } else {
+          debug!("Successfully handled Nouns vote: {:?}", vote.id);
+        }
+      }
+      voted_ids.push(proposal.id.clone());
+      changed = true;
+    }
+
+    if changed {
+      self
+        .cache
+        .put(&self.space.cache_key("proposals:nouns_votes"), &voted_ids)
+        .await;
+      info!("Updated Nouns votes in cache");
+    }
+
+    Ok(())
+  }
+
   pub async fn start(&self) -> Result<()> {
     self.setup().await;
 
@@ -95,16 +706,17 @@ impl MetaGov {
       debug!("Fetched {:?} proposals.", proposals.len());
 
       let mut new_proposals = Vec::new();
+      let mut changed_proposals = false;
 
       if let Some(old_proposals) = self
         .cache
-        .get::<Vec<Proposal>>("meta_gov:proposals")
+        .get::<Vec<Proposal>>(&self.space.cache_key("proposals"))
         .await?
       {
         let old_ids: Vec<_> = old_proposals.iter().map(|proposal| &proposal.id).collect();
         new_proposals = proposals
           .iter()
-          .filter(|proposal| !old_ids.contains(&&proposal.id))
+          .filter(|proposal| !old_ids.contains(&&proposal.id) && proposal.state != "closed")
           .cloned()
           .collect();
 
@@ -112,54 +724,165 @@ impl MetaGov {
 
         for proposal in &new_proposals {
           info!("Handling a new proposal... ({:?})", proposal.id);
+          let nouns_proposal = self.fetch_nouns_proposal(proposal).await;
           for handler in &self.handlers {
-            if let Err(err) = handler.handle_new_proposal(proposal).await {
+            if let Err(err) = handler
+              .handle_new_proposal(proposal, nouns_proposal.as_ref())
+              .await
+            {
               error!("Failed to handle new proposal: {:?}", err);
             } else {
               debug!("Successfully handled new proposal: {:?}", proposal.id);
             }
           }
         }
+
+        changed_proposals = proposals.iter().any(|proposal| {
+          old_proposals
+            .iter()
+            .find(|old_proposal| old_proposal.id == proposal.id)
+            .is_some_and(|old_proposal| {
+              old_proposal.state != proposal.state
+                || old_proposal.scores_state != proposal.scores_state
+            })
+        });
       }
 
-      if !new_proposals.is_empty() {
-        self.cache.put("meta_gov:proposals", &proposals).await;
+      if !new_proposals.is_empty() || changed_proposals {
+        self
+          .cache
+          .put(&self.space.cache_key("proposals"), &proposals)
+          .await;
         info!("Updated proposals in cache");
       }
+
+      let mut closed_ids = self
+        .cache
+        .get::<Vec<String>>(&self.space.cache_key("proposals:closed"))
+        .await?
+        .unwrap_or_default();
+
+      let closed_proposals: Vec<_> = proposals
+        .iter()
+        .filter(|proposal| proposal.is_finalized() && !closed_ids.contains(&proposal.id))
+        .cloned()
+        .collect();
+
+      debug!("Found {:?} closed proposals.", closed_proposals.len());
+
+      for proposal in &closed_proposals {
+        info!("Handling a closed proposal... ({:?})", proposal.id);
+        let nouns_proposal = self.fetch_nouns_proposal(proposal).await;
+        for handler in &self.handlers {
+          if let Err(err) = handler
+            .handle_closed_proposal(proposal, nouns_proposal.as_ref())
+            .await
+          {
+            error!("Failed to handle closed proposal: {:?}", err);
+          } else {
+            debug!("Successfully handled closed proposal: {:?}", proposal.id);
+          }
+        }
+        closed_ids.push(proposal.id.clone());
+      }
+
+      if !closed_proposals.is_empty() {
+        self
+          .cache
+          .put(&self.space.cache_key("proposals:closed"), &closed_ids)
+          .await;
+        info!("Updated closed proposals in cache");
+      }
+
+      if !self.reminder_hours.is_empty() {
+        self.check_closing_proposals(&proposals).await?;
+      }
+
+      if self.space.nouns_mirror && !self.space.nouns_delegate.is_empty() {
+        self.check_nouns_votes(&proposals).await?;
+      }
     } else {
       warn!("Failed to fetch proposals");
     }
 
-    if let Some(votes) = self.fetcher.fetch_votes().await {
-      debug!("Fetched {:?} votes.", votes.len());
+    let cursor = self
+      .cache
+      .get::<i64>(&self.space.cache_key("votes:cursor"))
+      .await?
+      .unwrap_or_else(|| Utc::now().timestamp());
 
-      let mut new_votes = Vec::new();
+    // Votes cast in the cursor's second were handled on the previous run.
+    let cursor_ids = self
+      .cache
+      .get::<Vec<String>>(&self.space.cache_key("votes:cursor_ids"))
+      .await?
+      .unwrap_or_default();
 
-      if let Some(old_votes) = self.cache.get::<Vec<Vote>>("meta_gov:votes").await? {
-        let old_ids: Vec<_> = old_votes.iter().map(|vote| &vote.id).collect();
-        new_votes = votes
-          .iter()
-          .filter(|vote| !old_ids.contains(&&vote.id))
-          .cloned()
-          .collect();
+    if let Some(votes) = self.fetcher.fetch_votes(cursor).await {
+      let new_votes: Vec<Vote> = votes
+        .iter()
+        .filter(|vote| !cursor_ids.contains(&vote.id))
+        .cloned()
+        .collect();
+      debug!("Found {:?} new votes.", new_votes.len());
+
+      let (large_votes, small_votes): (Vec<Vote>, Vec<Vote>) = new_votes
+        .iter()
+        .cloned()
+        .partition(|vote| vote.vp >= self.min_voting_power);
 
-        debug!("Found {:?} new votes.", new_votes.len());
+      for vote in &large_votes {
+        info!("Handling a new vote...");
+        for handler in &self.handlers {
+          if let Err(err) = handler.handle_new_vote(vote).await {
+            error!("Failed to handle new vote: {:?}", err);
+          } else {
+            debug!("Successfully handled new vote: {:?}", vote.id);
+          }
+        }
+      }
 
-        for vote in &new_votes {
-          info!("Handling a new vote...");
+      debug!(
+        "Found {:?} votes below the minimum voting power.",
+        small_votes.len()
+      );
+
+      if self.batch_small_votes {
+        let mut batches: HashMap<&str, Vec<Vote>> = HashMap::new();
+        for vote in &small_votes {
+          batches
+            .entry(vote.proposal_id.as_str())
+            .or_default()
+            .push(vote.clone());
+        }
+
+        for (proposal_id, votes) in &batches {
+          info!("Handling a batch of small votes... ({:?})", proposal_id);
           for handler in &self.handlers {
-            if let Err(err) = handler.handle_new_vote(vote).await {
-              error!("Failed to handle new vote: {:?}", err);
+            if let Err(err) = handler.handle_batched_votes(proposal_id, votes).await {
+              error!("Failed to handle batched votes: {:?}", err);
             } else {
-              debug!("Successfully handled new vote: {:?}", vote.id);
+              debug!("Successfully handled batched votes: {:?}", proposal_id);
             }
           }
         }
       }
 
-      if !new_votes.is_empty() {
-        self.cache.put("meta_gov:votes", &votes).await;
-        info!("Updated votes in cache");
+      if let Some(cursor) = votes.iter().map(|vote| vote.created).max() {
+        let cursor_ids: Vec<String> = votes
+          .iter()
+          .filter(|vote| vote.created == cursor)
+          .map(|vote| vote.id.clone())
+          .collect();
+        self
+          .cache
+          .put(&self.space.cache_key("votes:cursor"), &cursor)
+          .await;
+        self
+          .cache
+          .put(&self.space.cache_key("votes:cursor_ids"), &cursor_ids)
+          .await;
+        info!("Updated votes cursor in cache");
       }
     } else {
       warn!("Failed to fetch votes");
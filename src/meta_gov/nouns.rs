@@ -0,0 +1,94 @@
+use graphql_client::{reqwest::post_graphql, GraphQLQuery};
+use log::{debug, error};
+use reqwest::Client;
+use worker::{Env, Result};
+
+use crate::meta_gov::{NounsProposal, NounsVote};
+
+type BigInt = String;
+type Bytes = String;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+  schema_path = "graphql/schemas/nouns_schema.graphql",
+  query_path = "graphql/queries/nouns_query.graphql",
+  response_derives = "Clone, Debug",
+  skip_serializing_none,
+  deprecated = "warn"
+)]
+struct ProposalQuery;
+
+pub struct NounsFetcher {
+  graphql_url: String,
+}
+
+impl NounsFetcher {
+  pub fn new(graphql_url: String) -> Self {
+    Self { graphql_url }
+  }
+
+  pub fn new_from_env(env: &Env) -> Result<NounsFetcher> {
+    let graphql_url = env.var("META_GOV_NOUNS_GRAPHQL_URL")?.to_string();
+
+    Ok(Self::new(graphql_url))
+  }
+
+  async fn fetch<QueryType: GraphQLQuery>(
+    &self,
+    variables: <QueryType as GraphQLQuery>::Variables,
+  ) -> Option<<QueryType as GraphQLQuery>::ResponseData> {
+    let client = Client::builder()
+      .build()
+      .map_err(|e| {
+        error!("Failed to create client: {}", e);
+        debug!("Error details: {:?}", e);
+      })
+      .ok()?;
+
+    post_graphql::<QueryType, _>(&client, &self.graphql_url, variables)
+      .await
+      .map_err(|e| {
+        error!("Failed to execute GraphQL request: {}", e);
+        debug!("Failure details: {:?}", e);
+      })
+      .ok()
+      .and_then(|response| response.data)
+  }
+
+  /// Fetches a proposal together with the votes cast by the given delegate.
+  pub async fn fetch_proposal(&self, id: u32, delegate: &str) -> Option<NounsProposal> {
+    let variables = proposal_query::Variables {
+      id: id.to_string(),
+      delegate: delegate.to_lowercase(),
+    };
+
+    let response = self.fetch::<ProposalQuery>(variables).await?;
+    let proposal = response.proposal?;
+
+    let votes = proposal
+      .votes
+      .iter()
+      .map(|vote| NounsVote {
+        id: vote.id.clone(),
+        voter: vote.voter.id.clone(),
+        support: vote.support_detailed,
+        votes: vote.votes.clone(),
+        reason: vote.reason.clone(),
+        block_number: vote.block_number.clone(),
+      })
+      .collect();
+
+    Some(NounsProposal {
+      id: proposal.id.parse().unwrap_or(id),
+      title: proposal.title.clone(),
+      status: format!("{:?}", proposal.status),
+      end_block: proposal.end_block.clone(),
+      objection_period_end_block: proposal.objection_period_end_block.clone(),
+      for_votes: proposal.for_votes.clone(),
+      against_votes: proposal.against_votes.clone(),
+      abstain_votes: proposal.abstain_votes.clone(),
+      quorum_votes: proposal.quorum_votes.clone().unwrap_or_default(),
+      votes,
+    })
+  }
+}
@@ -1,9 +1,11 @@
+use async_trait::async_trait;
+use chrono::DateTime as ChronoDateTime;
 use graphql_client::{reqwest::post_graphql, GraphQLQuery};
 use log::{debug, error};
 use reqwest::Client;
 use worker::{Env, Result};
 
-use crate::prop_house::{Auction, Proposal, Vote};
+use crate::prop_house::{fetcher::Fetcher, Auction, Proposal, Vote};
 
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -37,6 +39,12 @@ struct VoteQuery;
 
 type DateTime = String;
 
+fn parse_timestamp(date: &str) -> i64 {
+  ChronoDateTime::parse_from_rfc3339(date)
+    .map(|date| date.timestamp())
+    .unwrap_or_default()
+}
+
 pub struct GraphQLFetcher {
   graphql_url: String,
   community_id: String,
@@ -78,8 +86,11 @@ impl GraphQLFetcher {
       .ok()
       .and_then(|response| response.data)
   }
+}
 
-  pub async fn fetch_auctions(&self) -> Option<Vec<Auction>> {
+#[async_trait(? Send)]
+impl Fetcher for GraphQLFetcher {
+  async fn fetch_auctions(&self) -> Option<Vec<Auction>> {
     let variables = auction_query::Variables {
       id: self.community_id.parse().unwrap(),
     };
@@ -90,16 +101,30 @@ impl GraphQLFetcher {
       .auctions
       .iter()
       .map(|auction| Auction {
-        id: auction.id.try_into().unwrap(),
+        id: auction.id.to_string(),
         title: auction.title.clone(),
         description: auction.description.clone(),
+        address: None,
+        start_time: parse_timestamp(&auction.start_time),
+        proposal_end_time: parse_timestamp(&auction.proposal_end_time),
+        voting_end_time: parse_timestamp(&auction.voting_end_time),
+        funding_amount: auction.funding_amount,
+        currency_type: auction.currency_type.clone(),
+        num_winners: auction.num_winners.try_into().unwrap(),
+        status: match &auction.status {
+          auction_query::AuctionStatus::Upcoming => "Upcoming".to_string(),
+          auction_query::AuctionStatus::Open => "Open".to_string(),
+          auction_query::AuctionStatus::Voting => "Voting".to_string(),
+          auction_query::AuctionStatus::Closed => "Closed".to_string(),
+          auction_query::AuctionStatus::Other(status) => status.clone(),
+        },
       })
       .collect();
 
     Some(auctions)
   }
 
-  pub async fn fetch_proposals(&self) -> Option<Vec<Proposal>> {
+  async fn fetch_proposals(&self) -> Option<Vec<Proposal>> {
     let variables = proposal_query::Variables {
       id: self.community_id.parse().unwrap(),
     };
@@ -114,21 +139,23 @@ impl GraphQLFetcher {
         auction
           .proposals
           .iter()
-          .map(move |proposal| (auction.auction_fragment.id.try_into().unwrap(), proposal))
+          .map(move |proposal| (auction.auction_fragment.id.to_string(), proposal))
       })
       .map(|(auction_id, proposal)| Proposal {
-        id: proposal.id.try_into().unwrap(),
+        id: proposal.id.to_string(),
         title: proposal.title.clone(),
         tldr: proposal.tldr.clone(),
         address: proposal.address.clone(),
         auction_id,
+        number: None,
+        vote_count: proposal.vote_count_for.try_into().unwrap(),
       })
       .collect();
 
     Some(proposals)
   }
 
-  pub async fn fetch_votes(&self) -> Option<Vec<Vote>> {
+  async fn fetch_votes(&self) -> Option<Vec<Vote>> {
     let variables = vote_query::Variables {
       id: self.community_id.parse().unwrap(),
     };
@@ -142,11 +169,12 @@ impl GraphQLFetcher {
       .flat_map(|auction| &auction.proposals)
       .flat_map(|proposal| &proposal.votes)
       .map(|vote| Vote {
-        id: vote.id.try_into().unwrap(),
+        id: vote.id.to_string(),
         address: vote.address.clone(),
-        auction_id: vote.auction_id.try_into().unwrap(),
-        proposal_id: vote.proposal_id.try_into().unwrap(),
-        direction: vote.direction.try_into().unwrap(),
+        auction_id: vote.auction_id.to_string(),
+        proposal_id: vote.proposal_id.to_string(),
+        direction: Some(vote.direction.try_into().unwrap()),
+        weight: vote.weight.try_into().unwrap(),
       })
       .collect();
 
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+
+use crate::prop_house::{Auction, Proposal, Vote};
+
+pub(crate) mod legacy;
+pub(crate) mod protocol;
+
+#[async_trait(? Send)]
+pub trait Fetcher {
+  async fn fetch_auctions(&self) -> Option<Vec<Auction>>;
+  async fn fetch_proposals(&self) -> Option<Vec<Proposal>>;
+  async fn fetch_votes(&self) -> Option<Vec<Vote>>;
+}
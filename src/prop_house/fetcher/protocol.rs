@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use graphql_client::{reqwest::post_graphql, GraphQLQuery};
+use log::{debug, error};
+use reqwest::Client;
+use worker::{Env, Result};
+
+use crate::{
+  prop_house::{fetcher::Fetcher, Auction, Proposal, Vote},
+  utils::get_short_address,
+};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+  schema_path = "graphql/schemas/prop_house_protocol_schema.graphql",
+  query_path = "graphql/queries/prop_house_protocol_query.graphql",
+  response_derives = "Clone",
+  skip_serializing_none,
+  deprecated = "warn"
+)]
+struct RoundQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+  schema_path = "graphql/schemas/prop_house_protocol_schema.graphql",
+  query_path = "graphql/queries/prop_house_protocol_query.graphql",
+  response_derives = "Clone",
+  skip_serializing_none,
+  deprecated = "warn"
+)]
+struct ProposalQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+  schema_path = "graphql/schemas/prop_house_protocol_schema.graphql",
+  query_path = "graphql/queries/prop_house_protocol_query.graphql",
+  response_derives = "Clone, Debug",
+  skip_serializing_none,
+  deprecated = "warn"
+)]
+struct VoteQuery;
+
+type BigInt = String;
+type Bytes = String;
+
+/// The largest page the subgraph serves; results are paged by id.
+const PAGE_SIZE: i64 = 1000;
+
+fn round_status(start_time: i64, proposal_end_time: i64, voting_end_time: i64) -> String {
+  let now = Utc::now().timestamp();
+
+  match now {
+    now if now < start_time => "Upcoming",
+    now if now < proposal_end_time => "Open",
+    now if now < voting_end_time => "Voting",
+    _ => "Closed",
+  }
+  .to_string()
+}
+
+pub struct ProtocolFetcher {
+  graphql_url: String,
+  house_address: String,
+}
+
+impl ProtocolFetcher {
+  pub fn new(graphql_url: String, house_address: String) -> Self {
+    Self {
+      graphql_url,
+      house_address,
+    }
+  }
+
+  pub fn new_from_env(env: &Env) -> Result<ProtocolFetcher> {
+    let graphql_url = env.var("PROP_HOUSE_PROTOCOL_GRAPHQL_URL")?.to_string();
+    let house_address = env.var("PROP_HOUSE_HOUSE_ADDRESS")?.to_string();
+
+    Ok(Self::new(graphql_url, house_address.to_lowercase()))
+  }
+
+  async fn fetch<QueryType: GraphQLQuery>(
+    &self,
+    variables: <QueryType as GraphQLQuery>::Variables,
+  ) -> Option<<QueryType as GraphQLQuery>::ResponseData> {
+    let client = Client::builder()
+      .build()
+      .map_err(|e| {
+        error!("Failed to create client: {}", e);
+        debug!("Error details: {:?}", e);
+      })
+      .ok()?;
+
+    post_graphql::<QueryType, _>(&client, &self.graphql_url, variables)
+      .await
+      .map_err(|e| {
+        error!("Failed to execute GraphQL request: {}", e);
+        debug!("Failure details: {:?}", e);
+      })
+      .ok()
+      .and_then(|response| response.data)
+  }
+}
+
+#[async_trait(? Send)]
+impl Fetcher for ProtocolFetcher {
+  async fn fetch_auctions(&self) -> Option<Vec<Auction>> {
+    let mut rounds = Vec::new();
+    let mut last_id = String::new();
+
+    loop {
+      let variables = round_query::Variables {
+        house: self.house_address.clone(),
+        first: PAGE_SIZE,
+        last_id: last_id.clone(),
+      };
+
+      let response = self.fetch::<RoundQuery>(variables).await?;
+      let page_size = response.rounds.len() as i64;
+      debug!("Fetched a page of {:?} rounds.", page_size);
+
+      if let Some(round) = response.rounds.last() {
+        last_id = round.id.clone();
+      }
+      rounds.extend(response.rounds);
+
+      if page_size < PAGE_SIZE {
+        break;
+      }
+    }
+
+    let auctions = rounds
+      .iter()
+      .filter_map(|round| {
+        let config = round.timed_config.as_ref()?;
+        let start_time = config.proposal_period_start_timestamp.parse().ok()?;
+        let proposal_end_time = config.proposal_period_end_timestamp.parse().ok()?;
+        let voting_end_time = config.vote_period_end_timestamp.parse().ok()?;
+
+        let (funding_amount, currency_type) = match config.awards.first() {
+          Some(award) => match award.asset.asset_type {
+            round_query::AssetType::Native => (
+              award.amount.parse::<f64>().unwrap_or_default() / 1e18,
+              "ETH".to_string(),
+            ),
+            _ => (
+              award.amount.parse::<f64>().unwrap_or_default(),
+              get_short_address(&award.asset.token),
+            ),
+          },
+          None => (0.0, String::new()),
+        };
+
+        Some(Auction {
+          id: round.id.clone(),
+          title: round.title.clone(),
+          description: round.description.clone(),
+          address: Some(round.id.clone()),
+          start_time,
+          proposal_end_time,
+          voting_end_time,
+          funding_amount,
+          currency_type,
+          num_winners: config.winner_count.try_into().ok()?,
+          status: round_status(start_time, proposal_end_time, voting_end_time),
+        })
+      })
+      .collect();
+
+    Some(auctions)
+  }
+
+  async fn fetch_proposals(&self) -> Option<Vec<Proposal>> {
+    let mut proposals = Vec::new();
+    let mut last_id = String::new();
+
+    loop {
+      let variables = proposal_query::Variables {
+        house: self.house_address.clone(),
+        first: PAGE_SIZE,
+        last_id: last_id.clone(),
+      };
+
+      let response = self.fetch::<ProposalQuery>(variables).await?;
+      let page_size = response.proposals.len() as i64;
+      debug!("Fetched a page of {:?} proposals.", page_size);
+
+      if let Some(proposal) = response.proposals.last() {
+        last_id = proposal.id.clone();
+      }
+      proposals.extend(
+        response
+          .proposals
+          .iter()
+          .filter(|proposal| !proposal.is_cancelled)
+          .map(|proposal| Proposal {
+            id: proposal.id.clone(),
+            title: proposal.title.clone(),
+            tldr: String::new(),
+            address: proposal.proposer.id.clone(),
+            auction_id: proposal.round.id.clone(),
+            number: proposal.proposal_id.parse().ok(),
+            vote_count: proposal.voting_power.parse().unwrap_or_default(),
+          }),
+      );
+
+      if page_size < PAGE_SIZE {
+        break;
+      }
+    }
+
+    Some(proposals)
+  }
+
+  async fn fetch_votes(&self) -> Option<Vec<Vote>> {
+    let mut votes = Vec::new();
+    let mut last_id = String::new();
+
+    loop {
+      let variables = vote_query::Variables {
+        house: self.house_address.clone(),
+        first: PAGE_SIZE,
+        last_id: last_id.clone(),
+      };
+
+      let response = self.fetch::<VoteQuery>(variables).await?;
+      let page_size = response.votes.len() as i64;
+      debug!("Fetched a page of {:?} votes.", page_size);
+
+      if let Some(vote) = response.votes.last() {
+        last_id = vote.id.clone();
+      }
+      votes.extend(response.votes.iter().map(|vote| Vote {
+        id: vote.id.clone(),
+        address: vote.voter.id.clone(),
+        auction_id: vote.round.id.clone(),
+        proposal_id: vote.proposal.id.clone(),
+        direction: None,
+        weight: vote.voting_power.parse().unwrap_or_default(),
+      }));
+
+      if page_size < PAGE_SIZE {
+        break;
+      }
+    }
+
+    Some(votes)
+  }
+}
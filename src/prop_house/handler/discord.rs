@@ -7,21 +7,21 @@ use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  prop_house::{handler::Handler, Auction, Proposal, Vote},
+  prop_house::{format_votes, handler::Handler, url::UrlBuilder, Auction, Proposal, Vote},
   utils::{ens::get_domain_name, get_explorer_address, get_short_address},
 };
 
 pub struct DiscordHandler {
-  base_url: String,
+  urls: UrlBuilder,
   webhook_url: String,
   cache: Cache,
   client: Client,
 }
 
 impl DiscordHandler {
-  pub fn new(base_url: String, webhook_url: String, cache: Cache, client: Client) -> Self {
+  pub fn new(urls: UrlBuilder, webhook_url: String, cache: Cache, client: Client) -> Self {
     Self {
-      base_url,
+      urls,
       webhook_url,
       cache,
       client,
@@ -29,13 +29,23 @@ impl DiscordHandler {
   }
 
   pub fn new_from_env(env: &Env) -> Result<DiscordHandler> {
-    let base_url = env.var("PROP_HOUSE_BASE_URL")?.to_string();
+    let urls = UrlBuilder::new_from_env(env)?;
     let webhook_url = env.secret("PROP_HOUSE_DISCORD_WEBHOOK_URL")?.to_string();
 
     let cache = Cache::new_from_env(env);
     let client = Client::new();
 
-    Ok(Self::new(base_url, webhook_url, cache, client))
+    Ok(Self::new(urls, webhook_url, cache, client))
+  }
+
+  async fn auctions(&self) -> Vec<Auction> {
+    self
+      .cache
+      .get::<Vec<Auction>>("prop_house:auctions")
+      .await
+      .ok()
+      .flatten()
+      .unwrap_or_default()
   }
 
   async fn execute_webhook(&self, embed: Value) -> Result<()> {
@@ -66,11 +76,7 @@ impl Handler for DiscordHandler {
   async fn handle_new_auction(&self, auction: &Auction) -> Result<()> {
     info!("Handling new auction: {}", auction.title);
 
-    let url = format!(
-      "{}/{}",
-      self.base_url,
-      auction.title.replace(' ', "-").to_lowercase()
-    );
+    let url = self.urls.round_url(auction, &self.auctions().await);
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
     let description = format!(
       "A new Prop House round has been created: “{}”",
@@ -82,7 +88,166 @@ impl Handler for DiscordHandler {
         "description": description,
         "url": url,
         "color": 0x8A2CE2,
-        "footer": {"text": date}
+        "footer": {"text": date},
+        "fields": [
+            {"name": "Proposing Opens", "value": format!("<t:{}:R>", auction.start_time), "inline": true},
+            {"name": "Proposing Closes", "value": format!("<t:{}:R>", auction.proposal_end_time), "inline": true},
+            {"name": "Voting Ends", "value": format!("<t:{}:R>", auction.voting_end_time), "inline": true},
+            {"name": "Funding", "value": auction.funding_details()}
+        ]
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_opened(&self, auction: &Auction) -> Result<()> {
+    info!("Handling opened auction: {}", auction.title);
+
+    let url = self.urls.round_url(auction, &self.auctions().await);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let description = format!(
+      "The Prop House round “{}” is now accepting proposals.",
+      auction.title
+    );
+
+    let embed = json!({
+        "title": "Prop House Round Open",
+        "description": description,
+        "url": url,
+        "color": 0x8A2CE2,
+        "footer": {"text": date},
+        "fields": [
+            {"name": "Proposing Closes", "value": format!("<t:{}:R>", auction.proposal_end_time), "inline": true},
+            {"name": "Voting Ends", "value": format!("<t:{}:R>", auction.voting_end_time), "inline": true},
+            {"name": "Funding", "value": auction.funding_details()}
+        ]
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_voting(&self, auction: &Auction) -> Result<()> {
+    info!("Handling voting auction: {}", auction.title);
+
+    let url = self.urls.round_url(auction, &self.auctions().await);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let description = format!(
+      "Proposing has closed for the Prop House round “{}” and voting has begun.",
+      auction.title
+    );
+
+    let embed = json!({
+        "title": "Prop House Round Voting",
+        "description": description,
+        "url": url,
+        "color": 0x8A2CE2,
+        "footer": {"text": date},
+        "fields": [
+            {"name": "Voting Ends", "value": format!("<t:{}:R>", auction.voting_end_time), "inline": true},
+            {"name": "Funding", "value": auction.funding_details()}
+        ]
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_closed(&self, auction: &Auction, winners: &[Proposal]) -> Result<()> {
+    info!("Handling closed auction: {}", auction.title);
+
+    let auctions = self.auctions().await;
+    let url = self.urls.round_url(auction, &auctions);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+
+    let mut lines = Vec::new();
+    for (rank, proposal) in winners.iter().enumerate() {
+      let wallet = get_domain_name(&proposal.address)
+        .await
+        .unwrap_or(get_short_address(&proposal.address));
+      lines.push(format!(
+        "{}. [{}]({}) by {} ({})",
+        rank + 1,
+        proposal.title,
+        self.urls.proposal_url(auction, proposal, &auctions),
+        wallet,
+        proposal.format_votes()
+      ));
+    }
+
+    let description = match lines.is_empty() {
+      true => format!(
+        "Voting has ended for the Prop House round “{}” without any winners.",
+        auction.title
+      ),
+      false => format!(
+        "Voting has ended for the Prop House round “{}”. The winners are:\n\n{}",
+        auction.title,
+        lines.join("\n")
+      ),
+    };
+
+    let embed = json!({
+        "title": "Prop House Round Closed",
+        "description": description,
+        "url": url,
+        "color": 0x8A2CE2,
+        "footer": {"text": date},
+        "fields": [
+            {"name": "Funding", "value": auction.funding_details()}
+        ]
+    });
+
+    self.execute_webhook(embed).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_standings(
+    &self,
+    auction: &Auction,
+    standings: &[Proposal],
+  ) -> Result<()> {
+    info!("Handling auction standings: {}", auction.title);
+
+    let auctions = self.auctions().await;
+    let url = self.urls.round_url(auction, &auctions);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+
+    let lines: Vec<_> = standings
+      .iter()
+      .enumerate()
+      .map(|(rank, proposal)| {
+        format!(
+          "{}. [{}]({}) ({})",
+          rank + 1,
+          proposal.title,
+          self.urls.proposal_url(auction, proposal, &auctions),
+          proposal.format_votes()
+        )
+      })
+      .collect();
+
+    let description = format!(
+      "Current standings in the Prop House round “{}”:\n\n{}",
+      auction.title,
+      lines.join("\n")
+    );
+
+    let embed = json!({
+        "title": "Prop House Round Standings",
+        "description": description,
+        "url": url,
+        "color": 0x8A2CE2,
+        "footer": {"text": date},
+        "fields": [
+            {"name": "Voting Ends", "value": format!("<t:{}:R>", auction.voting_end_time), "inline": true},
+            {"name": "Funding", "value": auction.funding_details(), "inline": true}
+        ]
     });
 
     self.execute_webhook(embed).await?;
@@ -105,12 +270,7 @@ impl Handler for DiscordHandler {
       .unwrap()
       .clone();
 
-    let url = format!(
-      "{}/{}/{}",
-      self.base_url,
-      auction.title.replace(' ', "-").to_lowercase(),
-      proposal.id
-    );
+    let url = self.urls.proposal_url(&auction, proposal, &auctions);
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
     let wallet = get_domain_name(&proposal.address)
       .await
@@ -153,25 +313,26 @@ impl Handler for DiscordHandler {
       .unwrap()
       .clone();
 
-    let url = format!(
-      "{}/{}/{}",
-      self.base_url,
-      proposal.title.replace(' ', "-").to_lowercase(),
-      proposal.id
-    );
+    let auctions = self.auctions().await;
+    let url = match auctions.iter().find(|&a| a.id == proposal.auction_id) {
+      Some(auction) => self.urls.proposal_url(auction, &proposal, &auctions),
+      None => self.urls.base_url(),
+    };
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
     let wallet = get_domain_name(&vote.address)
       .await
       .unwrap_or(get_short_address(&vote.address));
 
     let description = format!(
-      "{} has voted {} “{}” proposal.",
+      "{} has voted {} “{}” proposal with {}.",
       wallet,
       match vote.direction {
-        1 => "for",
-        _ => "against",
+        Some(1) => "for",
+        Some(_) => "against",
+        None => "on",
       },
-      proposal.title
+      proposal.title,
+      format_votes(vote.weight)
     );
     let explorer = get_explorer_address(&vote.address);
 
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
 use log::{debug, error, info};
 use reqwest::{
   header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE},
@@ -12,12 +13,12 @@ use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  prop_house::{handler::Handler, Auction, Proposal, Vote},
+  prop_house::{format_votes, handler::Handler, url::UrlBuilder, Auction, Proposal, Vote},
   utils::fname::get_username_by_address,
 };
 
 pub(crate) struct FarcasterHandler {
-  base_url: String,
+  urls: UrlBuilder,
   warpcast_url: String,
   warpcast_bearer_token: String,
   warpcast_channel_key: String,
@@ -28,7 +29,7 @@ pub(crate) struct FarcasterHandler {
 
 impl FarcasterHandler {
   pub fn new(
-    base_url: String,
+    urls: UrlBuilder,
     warpcast_url: String,
     warpcast_bearer_token: String,
     warpcast_channel_key: String,
@@ -37,7 +38,7 @@ impl FarcasterHandler {
     client: Client,
   ) -> Self {
     Self {
-      base_url,
+      urls,
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
@@ -48,7 +49,7 @@ impl FarcasterHandler {
   }
 
   pub fn new_from_env(env: &Env) -> Result<FarcasterHandler> {
-    let base_url = env.var("PROP_HOUSE_BASE_URL")?.to_string();
+    let urls = UrlBuilder::new_from_env(env)?;
     let warpcast_url = env.var("WARPCAST_API_BASE_URL")?.to_string();
     let warpcast_bearer_token = env.secret("PROP_HOUSE_WARPCAST_TOKEN")?.to_string();
     let warpcast_channel_key = env.var("PROP_HOUSE_WARPCAST_CHANNEL")?.to_string();
@@ -58,7 +59,7 @@ impl FarcasterHandler {
     let client = Client::new();
 
     Ok(Self::new(
-      base_url,
+      urls,
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
@@ -68,6 +69,16 @@ impl FarcasterHandler {
     ))
   }
 
+  async fn auctions(&self) -> Vec<Auction> {
+    self
+      .cache
+      .get::<Vec<Auction>>("prop_house:auctions")
+      .await
+      .ok()
+      .flatten()
+      .unwrap_or_default()
+  }
+
   async fn make_http_request(&self, request_data: Value) -> Result<Response> {
     let url = format!("{}/casts", self.warpcast_url);
     let token = format!("Bearer {}", self.warpcast_bearer_token);
@@ -98,19 +109,157 @@ impl FarcasterHandler {
   }
 }
 
+fn format_date(timestamp: i64) -> String {
+  Utc
+    .timestamp_opt(timestamp, 0)
+    .single()
+    .map(|date| date.format("%b %-d, %H:%M UTC").to_string())
+    .unwrap_or_default()
+}
+
 #[async_trait(? Send)]
 impl Handler for FarcasterHandler {
   async fn handle_new_auction(&self, auction: &Auction) -> Result<()> {
     info!("Handling new auction: {}", auction.title);
 
-    let url = format!(
-      "{}/{}",
-      self.base_url,
-      auction.title.replace(' ', "-").to_lowercase()
+    let url = self.urls.round_url(auction, &self.auctions().await);
+    let description = format!(
+      "A new Prop House round has been created: “{}”\n\nProposing: {} to {}\nVoting ends: \
+       {}\nFunding: {}",
+      auction.title,
+      format_date(auction.start_time),
+      format_date(auction.proposal_end_time),
+      format_date(auction.voting_end_time),
+      auction.funding_details()
     );
+
+    let request_data = json!({
+        "text": description,
+        "embeds": [url],
+        "channelKey": self.warpcast_channel_key
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_opened(&self, auction: &Auction) -> Result<()> {
+    info!("Handling opened auction: {}", auction.title);
+
+    let url = self.urls.round_url(auction, &self.auctions().await);
     let description = format!(
-      "A new Prop House round has been created: “{}”",
-      auction.title
+      "The Prop House round “{}” is now accepting proposals until {}. Voting ends {}.\n\nFunding: \
+       {}",
+      auction.title,
+      format_date(auction.proposal_end_time),
+      format_date(auction.voting_end_time),
+      auction.funding_details()
+    );
+
+    let request_data = json!({
+        "text": description,
+        "embeds": [url],
+        "channelKey": self.warpcast_channel_key
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_voting(&self, auction: &Auction) -> Result<()> {
+    info!("Handling voting auction: {}", auction.title);
+
+    let url = self.urls.round_url(auction, &self.auctions().await);
+    let description = format!(
+      "Proposing has closed for the Prop House round “{}” and voting is open until \
+       {}.\n\nFunding: {}",
+      auction.title,
+      format_date(auction.voting_end_time),
+      auction.funding_details()
+    );
+
+    let request_data = json!({
+        "text": description,
+        "embeds": [url],
+        "channelKey": self.warpcast_channel_key
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_closed(&self, auction: &Auction, winners: &[Proposal]) -> Result<()> {
+    info!("Handling closed auction: {}", auction.title);
+
+    let url = self.urls.round_url(auction, &self.auctions().await);
+
+    let mut lines = Vec::new();
+    for (rank, proposal) in winners.iter().enumerate() {
+      let wallet = get_username_by_address(self.farquest_api_key.as_str(), &proposal.address).await;
+      lines.push(format!(
+        "{}. “{}” by {} ({})",
+        rank + 1,
+        proposal.title,
+        wallet,
+        proposal.format_votes()
+      ));
+    }
+
+    let description = match lines.is_empty() {
+      true => format!(
+        "Voting has ended for the Prop House round “{}” without any winners.\n\nFunding: {}",
+        auction.title,
+        auction.funding_details()
+      ),
+      false => format!(
+        "Voting has ended for the Prop House round “{}”. The winners are:\n\n{}\n\nFunding: {}",
+        auction.title,
+        lines.join("\n"),
+        auction.funding_details()
+      ),
+    };
+
+    let request_data = json!({
+        "text": description,
+        "embeds": [url],
+        "channelKey": self.warpcast_channel_key
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_auction_standings(
+    &self,
+    auction: &Auction,
+    standings: &[Proposal],
+  ) -> Result<()> {
+    info!("Handling auction standings: {}", auction.title);
+
+    let url = self.urls.round_url(auction, &self.auctions().await);
+
+    let lines: Vec<_> = standings
+      .iter()
+      .enumerate()
+      .map(|(rank, proposal)| {
+        format!(
+          "{}. “{}” ({})",
+          rank + 1,
+          proposal.title,
+          proposal.format_votes()
+        )
+      })
+      .collect();
+
+    let description = format!(
+      "Current standings in the Prop House round “{}”, voting ends {}:\n\n{}",
+      auction.title,
+      format_date(auction.voting_end_time),
+      lines.join("\n")
     );
 
     let request_data = json!({
@@ -136,15 +285,9 @@ impl Handler for FarcasterHandler {
     let auction = auctions
       .iter()
       .find(|&a| a.id == proposal.auction_id)
-      .clone()
       .ok_or("Auction not found in the funding list.")?;
 
-    let url = format!(
-      "{}/{}/{}",
-      self.base_url,
-      auction.title.replace(' ', "-").to_lowercase(),
-      proposal.id
-    );
+    let url = self.urls.proposal_url(auction, proposal, &auctions);
 
     let wallet = get_username_by_address(self.farquest_api_key.as_str(), &proposal.address).await;
 
@@ -161,7 +304,7 @@ impl Handler for FarcasterHandler {
 
     let response = self.make_http_request(request_data).await.map_err(|e| {
       error!("Failed to make HTTP request: {}", e);
-      return e;
+      e
     })?;
 
     let response_body = response.text().await.map_err(|e| {
@@ -186,12 +329,12 @@ impl Handler for FarcasterHandler {
 
     let mut proposals_casts = self
       .cache
-      .get::<HashMap<isize, String>>("prop_house:proposals:casts")
+      .get::<HashMap<String, String>>("prop_house:proposals:casts")
       .await?
       .ok_or("Failed to retrieve proposals casts")?;
     debug!("Proposals casts before insertion: {:?}", proposals_casts);
 
-    proposals_casts.insert(proposal.id, cast_hash.to_string());
+    proposals_casts.insert(proposal.id.clone(), cast_hash.to_string());
     debug!("Proposals casts after insertion: {:?}", proposals_casts);
 
     let proposals_casts_as_string = to_string(&proposals_casts)?;
@@ -218,12 +361,11 @@ impl Handler for FarcasterHandler {
     let proposal = proposals
       .iter()
       .find(|&a| a.id == vote.proposal_id)
-      .clone()
       .ok_or("Proposal not found in the funding list.")?;
 
     let proposals_casts = self
       .cache
-      .get::<HashMap<isize, String>>("prop_house:proposals:casts")
+      .get::<HashMap<String, String>>("prop_house:proposals:casts")
       .await?
       .ok_or("Failed to retrieve proposals casts")?;
 
@@ -234,13 +376,15 @@ impl Handler for FarcasterHandler {
     let wallet = get_username_by_address(self.farquest_api_key.as_str(), &vote.address).await;
 
     let description = format!(
-      "{} has voted {} “{}” proposal.",
+      "{} has voted {} “{}” proposal with {}.",
       wallet,
       match vote.direction {
-        1 => "for",
-        _ => "against",
+        Some(1) => "for",
+        Some(_) => "against",
+        None => "on",
       },
-      proposal.title
+      proposal.title,
+      format_votes(vote.weight)
     );
 
     let request_data = json!({
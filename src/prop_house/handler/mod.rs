@@ -9,6 +9,11 @@ pub(crate) mod farcaster;
 #[async_trait(? Send)]
 pub trait Handler {
   async fn handle_new_auction(&self, auction: &Auction) -> Result<()>;
+  async fn handle_auction_opened(&self, auction: &Auction) -> Result<()>;
+  async fn handle_auction_voting(&self, auction: &Auction) -> Result<()>;
+  async fn handle_auction_closed(&self, auction: &Auction, winners: &[Proposal]) -> Result<()>;
+  async fn handle_auction_standings(&self, auction: &Auction, standings: &[Proposal])
+    -> Result<()>;
   async fn handle_new_proposal(&self, proposal: &Proposal) -> Result<()>;
   async fn handle_new_vote(&self, vote: &Vote) -> Result<()>;
 }
@@ -1,47 +1,133 @@
-use fetcher::GraphQLFetcher;
+use fetcher::{legacy::GraphQLFetcher, protocol::ProtocolFetcher, Fetcher};
 use handler::{discord::DiscordHandler, farcaster::FarcasterHandler, Handler};
 use log::{debug, error, info, warn};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use worker::{Env, Result};
 
 use crate::cache::Cache;
 
 pub mod fetcher;
 pub mod handler;
+pub mod url;
+
+/// Reads ids cached as numbers before the protocol backend's string ids.
+fn deserialize_id<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> std::result::Result<String, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Id {
+    Number(i64),
+    String(String),
+  }
+
+  Ok(match Id::deserialize(deserializer)? {
+    Id::Number(id) => id.to_string(),
+    Id::String(id) => id,
+  })
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Auction {
-  pub id: isize,
+  #[serde(deserialize_with = "deserialize_id")]
+  pub id: String,
   pub title: String,
   pub description: String,
+  #[serde(default)]
+  pub address: Option<String>,
+  #[serde(default)]
+  pub start_time: i64,
+  #[serde(default)]
+  pub proposal_end_time: i64,
+  #[serde(default)]
+  pub voting_end_time: i64,
+  #[serde(default)]
+  pub funding_amount: f64,
+  #[serde(default)]
+  pub currency_type: String,
+  #[serde(default)]
+  pub num_winners: isize,
+  #[serde(default)]
+  pub status: String,
+}
+
+impl Auction {
+  /// The proposals of the round with votes, ranked, up to the number of
+  /// winners.
+  pub fn winners(&self, proposals: &[Proposal]) -> Vec<Proposal> {
+    let mut ranked: Vec<_> = proposals
+      .iter()
+      .filter(|proposal| proposal.auction_id == self.id && proposal.vote_count > 0)
+      .cloned()
+      .collect();
+    ranked.sort_by(|a, b| b.vote_count.cmp(&a.vote_count).then(a.id.cmp(&b.id)));
+    ranked.truncate(self.num_winners.max(0) as usize);
+
+    ranked
+  }
+
+  pub fn funding_details(&self) -> String {
+    format!(
+      "{} × {} {}",
+      self.num_winners, self.funding_amount, self.currency_type
+    )
+  }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Proposal {
-  pub id: isize,
+  #[serde(deserialize_with = "deserialize_id")]
+  pub id: String,
   pub title: String,
   pub tldr: String,
   pub address: String,
-  pub auction_id: isize,
+  #[serde(deserialize_with = "deserialize_id")]
+  pub auction_id: String,
+  #[serde(default)]
+  pub number: Option<isize>,
+  #[serde(default)]
+  pub vote_count: isize,
+}
+
+pub fn format_votes(count: isize) -> String {
+  match count {
+    1 => "1 vote".to_string(),
+    count => format!("{} votes", count),
+  }
+}
+
+impl Proposal {
+  pub fn format_votes(&self) -> String {
+    format_votes(self.vote_count)
+  }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Vote {
-  pub id: isize,
+  #[serde(deserialize_with = "deserialize_id")]
+  pub id: String,
   pub address: String,
-  pub auction_id: isize,
-  pub proposal_id: isize,
-  pub direction: isize,
+  #[serde(deserialize_with = "deserialize_id")]
+  pub auction_id: String,
+  #[serde(deserialize_with = "deserialize_id")]
+  pub proposal_id: String,
+  /// Unset for protocol votes, which only ever allocate voting power.
+  #[serde(default)]
+  pub direction: Option<isize>,
+  #[serde(default)]
+  pub weight: isize,
 }
 
+const STANDINGS_SIZE: usize = 5;
+
 pub struct PropHouse {
   cache: Cache,
-  fetcher: GraphQLFetcher,
+  fetcher: Box<dyn Fetcher>,
   handlers: Vec<Box<dyn Handler>>,
 }
 
 impl PropHouse {
-  pub fn new(cache: Cache, fetcher: GraphQLFetcher, handlers: Vec<Box<dyn Handler>>) -> Self {
+  pub fn new(cache: Cache, fetcher: Box<dyn Fetcher>, handlers: Vec<Box<dyn Handler>>) -> Self {
     Self {
       cache,
       fetcher,
@@ -51,7 +137,10 @@ impl PropHouse {
 
   pub fn new_from_env(env: &Env) -> Result<Self> {
     let cache = Cache::new_from_env(env);
-    let fetcher = GraphQLFetcher::new_from_env(env)?;
+    let fetcher: Box<dyn Fetcher> = match env.var("PROP_HOUSE_BACKEND")?.to_string().as_str() {
+      "protocol" => Box::new(ProtocolFetcher::new_from_env(env)?),
+      _ => Box::new(GraphQLFetcher::new_from_env(env)?),
+    };
     let mut handlers = vec![];
 
     if env.var("PROP_HOUSE_DISCORD_ENABLED")?.to_string() == "true" {
@@ -103,6 +192,47 @@ impl PropHouse {
     debug!("Setup function finished.");
   }
 
+  pub async fn post_standings(&self) -> Result<()> {
+    debug!("Standings function started.");
+
+    let Some(auctions) = self.fetcher.fetch_auctions().await else {
+      warn!("Failed to fetch auctions");
+      return Ok(());
+    };
+    let Some(proposals) = self.fetcher.fetch_proposals().await else {
+      warn!("Failed to fetch proposals");
+      return Ok(());
+    };
+
+    for auction in auctions.iter().filter(|auction| auction.status == "Voting") {
+      let mut standings: Vec<_> = proposals
+        .iter()
+        .filter(|proposal| proposal.auction_id == auction.id)
+        .cloned()
+        .collect();
+      standings.sort_by(|a, b| b.vote_count.cmp(&a.vote_count).then(a.id.cmp(&b.id)));
+      standings.truncate(STANDINGS_SIZE);
+
+      if standings.is_empty() {
+        debug!("Skipping standings without proposals: {:?}", auction.id);
+        continue;
+      }
+
+      info!("Handling auction standings... ({:?})", auction.id);
+      for handler in &self.handlers {
+        if let Err(err) = handler.handle_auction_standings(auction, &standings).await {
+          error!("Failed to handle auction standings: {:?}", err);
+        } else {
+          debug!("Successfully handled auction standings: {:?}", auction.id);
+        }
+      }
+    }
+
+    debug!("Standings function finished.");
+
+    Ok(())
+  }
+
   pub async fn start(&self) -> Result<()> {
     self.setup().await;
 
@@ -112,6 +242,7 @@ impl PropHouse {
       debug!("Fetched {:?} auctions.", auctions.len());
 
       let mut new_auctions = Vec::new();
+      let mut updated_auctions = false;
 
       if let Some(old_auctions) = self
         .cache
@@ -137,9 +268,63 @@ impl PropHouse {
             }
           }
         }
+
+        let changed_auctions: Vec<_> = auctions
+          .iter()
+          .filter(|auction| {
+            old_auctions
+              .iter()
+              .find(|old_auction| old_auction.id == auction.id)
+              .is_some_and(|old_auction| {
+                !old_auction.status.is_empty() && old_auction.status != auction.status
+              })
+          })
+          .cloned()
+          .collect();
+
+        debug!("Found {:?} changed auctions.", changed_auctions.len());
+
+        let proposals = match changed_auctions
+          .iter()
+          .any(|auction| auction.status == "Closed")
+        {
+          true => self.fetcher.fetch_proposals().await.unwrap_or_default(),
+          false => Vec::new(),
+        };
+
+        for auction in &changed_auctions {
+          info!("Handling an auction status change... ({:?})", auction.id);
+          for handler in &self.handlers {
+            let result = match auction.status.as_str() {
+              "Open" => handler.handle_auction_opened(auction).await,
+              "Voting" => handler.handle_auction_voting(auction).await,
+              "Closed" => {
+                handler
+                  .handle_auction_closed(auction, &auction.winners(&proposals))
+                  .await
+              }
+              _ => Ok(()),
+            };
+            if let Err(err) = result {
+              error!("Failed to handle auction status change: {:?}", err);
+            } else {
+              debug!(
+                "Successfully handled auction status change: {:?}",
+                auction.id
+              );
+            }
+          }
+        }
+
+        updated_auctions = auctions.iter().any(|auction| {
+          old_auctions
+            .iter()
+            .find(|old_auction| old_auction.id == auction.id)
+            .is_some_and(|old_auction| old_auction.status != auction.status)
+        });
       }
 
-      if !new_auctions.is_empty() {
+      if !new_auctions.is_empty() || updated_auctions {
         self.cache.put("prop_house:auctions", &auctions).await;
         info!("Updated auctions in cache");
       }
@@ -0,0 +1,172 @@
+use reqwest::Url;
+use worker::{Env, Result};
+
+use crate::prop_house::{Auction, Proposal};
+
+/// Mirrors Prop House's own `nameToSlug`, escaped so any title is a valid
+/// path segment.
+pub fn slugify(title: &str) -> String {
+  title
+    .replace(' ', "-")
+    .to_lowercase()
+    .bytes()
+    .map(|byte| match byte {
+      b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+      _ => format!("%{:02X}", byte),
+    })
+    .collect()
+}
+
+pub struct UrlBuilder {
+  base_url: String,
+}
+
+impl UrlBuilder {
+  pub fn new(base_url: String) -> Self {
+    Self {
+      base_url: base_url.trim_end_matches('/').to_string(),
+    }
+  }
+
+  pub fn new_from_env(env: &Env) -> Result<UrlBuilder> {
+    let base_url = env.var("PROP_HOUSE_BASE_URL")?.to_string();
+
+    Ok(Self::new(base_url))
+  }
+
+  pub fn base_url(&self) -> String {
+    self.base_url.clone()
+  }
+
+  fn origin(&self) -> String {
+    Url::parse(&self.base_url)
+      .map(|url| url.origin().ascii_serialization())
+      .unwrap_or_else(|_| self.base_url.clone())
+  }
+
+  /// Round slugs are resolved by title, so a title shared with another round
+  /// cannot be linked to directly.
+  fn is_ambiguous(&self, auction: &Auction, auctions: &[Auction]) -> bool {
+    let slug = slugify(&auction.title);
+
+    auctions
+      .iter()
+      .any(|other| other.id != auction.id && slugify(&other.title) == slug)
+  }
+
+  pub fn round_url(&self, auction: &Auction, auctions: &[Auction]) -> String {
+    match &auction.address {
+      Some(address) => format!("{}/{}", self.origin(), address),
+      None if self.is_ambiguous(auction, auctions) => self.base_url.clone(),
+      None => format!("{}/{}", self.base_url, slugify(&auction.title)),
+    }
+  }
+
+  pub fn proposal_url(
+    &self,
+    auction: &Auction,
+    proposal: &Proposal,
+    auctions: &[Auction],
+  ) -> String {
+    match (&auction.address, proposal.number) {
+      (Some(address), Some(number)) => format!("{}/{}/{}", self.origin(), address, number),
+      (Some(_), None) => self.round_url(auction, auctions),
+      (None, _) if self.is_ambiguous(auction, auctions) => {
+        format!("{}/proposal/{}", self.origin(), proposal.id)
+      }
+      (None, _) => format!(
+        "{}/{}/{}",
+        self.base_url,
+        slugify(&auction.title),
+        proposal.id
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn auction(id: &str, title: &str, address: Option<&str>) -> Auction {
+    Auction {
+      id: id.to_string(),
+      title: title.to_string(),
+      description: String::new(),
+      address: address.map(str::to_string),
+      start_time: 0,
+      proposal_end_time: 0,
+      voting_end_time: 0,
+      funding_amount: 0.0,
+      currency_type: String::new(),
+      num_winners: 0,
+      status: String::new(),
+    }
+  }
+
+  fn proposal(id: &str, auction_id: &str, number: Option<isize>) -> Proposal {
+    Proposal {
+      id: id.to_string(),
+      title: String::new(),
+      tldr: String::new(),
+      address: String::new(),
+      auction_id: auction_id.to_string(),
+      number,
+      vote_count: 0,
+    }
+  }
+
+  fn builder() -> UrlBuilder {
+    UrlBuilder::new("https://prop.house/lil-nouns/".to_string())
+  }
+
+  #[test]
+  fn slugifies_titles_like_prop_house() {
+    assert_eq!(slugify("Lil Nouns Round 5"), "lil-nouns-round-5");
+    assert_eq!(slugify("What's Next?"), "what%27s-next%3F");
+    assert_eq!(slugify("Café Noun"), "caf%C3%A9-noun");
+    assert_eq!(slugify("Art / Design"), "art-%2F-design");
+  }
+
+  #[test]
+  fn builds_legacy_round_and_proposal_urls() {
+    let round = auction("1", "Lil Nouns Round 5", None);
+    let auctions = vec![round.clone()];
+
+    assert_eq!(
+      builder().round_url(&round, &auctions),
+      "https://prop.house/lil-nouns/lil-nouns-round-5"
+    );
+    assert_eq!(
+      builder().proposal_url(&round, &proposal("4242", "1", None), &auctions),
+      "https://prop.house/lil-nouns/lil-nouns-round-5/4242"
+    );
+  }
+
+  #[test]
+  fn falls_back_when_round_titles_collide() {
+    let first = auction("1", "Open Round", None);
+    let second = auction("2", "open round", None);
+    let auctions = vec![first.clone(), second];
+
+    assert_eq!(
+      builder().round_url(&first, &auctions),
+      "https://prop.house/lil-nouns"
+    );
+    assert_eq!(
+      builder().proposal_url(&first, &proposal("7", "1", None), &auctions),
+      "https://prop.house/proposal/7"
+    );
+  }
+
+  #[test]
+  fn builds_protocol_round_and_proposal_urls() {
+    let round = auction("1", "Round", Some("0xabc"));
+
+    assert_eq!(builder().round_url(&round, &[]), "https://prop.house/0xabc");
+    assert_eq!(
+      builder().proposal_url(&round, &proposal("99", "1", Some(3)), &[]),
+      "https://prop.house/0xabc/3"
+    );
+  }
+}
@@ -11,7 +11,7 @@ use crate::prop_lot::{Comment, Idea, Vote};
 #[graphql(
   schema_path = "graphql/schemas/prop_lot_schema.graphql",
   query_path = "graphql/queries/prop_lot_query.graphql",
-  response_derives = "Clone",
+  response_derives = "Clone, Debug",
   deprecated = "warn"
 )]
 struct IdeaQuery;
@@ -74,10 +74,21 @@ impl GraphQLFetcher {
   }
 
   pub async fn fetch_ideas(&self) -> Option<Vec<Idea>> {
+    self.fetch_ideas_sorted(idea_query::SORT_TYPE::OLDEST).await
+  }
+
+  /// Fetches ideas ordered by their vote count, most supported first.
+  pub async fn fetch_trending_ideas(&self) -> Option<Vec<Idea>> {
+    self
+      .fetch_ideas_sorted(idea_query::SORT_TYPE::VOTES_DESC)
+      .await
+  }
+
+  async fn fetch_ideas_sorted(&self, sort: idea_query::SORT_TYPE) -> Option<Vec<Idea>> {
     let variables = idea_query::Variables {
       options: idea_query::IdeaInputOptions {
         idea_id: None,
-        sort: Some(idea_query::SORT_TYPE::OLDEST),
+        sort: Some(sort),
       },
     };
 
@@ -92,6 +103,20 @@ impl GraphQLFetcher {
         title: idea.title.clone(),
         tldr: idea.tldr.clone(),
         creator_id: idea.creator_id.clone(),
+        votecount: idea.votecount.try_into().unwrap(),
+        consensus: idea.consensus,
+        closed: idea.closed,
+        tags: Some(
+          idea
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| match &tag.type_ {
+              idea_query::TagType::Other(tag_type) => tag_type.clone(),
+              tag_type => format!("{:?}", tag_type),
+            })
+            .collect(),
+        ),
       })
       .collect();
 
@@ -145,8 +170,10 @@ impl GraphQLFetcher {
       .map(|comment| Comment {
         id: comment.id.try_into().unwrap(),
         idea_id: comment.idea_id.try_into().unwrap(),
+        parent_id: comment.parent_id.and_then(|id| id.try_into().ok()),
         author_id: comment.author_id.clone(),
         body: comment.body.clone(),
+        deleted: comment.deleted,
       })
       .collect();
 
@@ -1,53 +1,136 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use chrono::Local;
-use log::{error, info};
-use reqwest::{header, Client};
-use serde_json::{json, Value};
+use log::{debug, error, info, warn};
+use reqwest::{header, Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_string, Value};
 use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  prop_lot::{handler::Handler, Comment, Idea, Vote},
+  lil_nouns::Proposal,
+  prop_lot::{handler::Handler, url::UrlBuilder, Comment, Idea, TrendingIdea, Vote},
   utils::{ens::get_domain_name, get_explorer_address, get_short_address},
 };
 
+/// A posted comment's message, with the route of the webhook it went through
+/// since the idea's webhook changes with its tags. Only the route is kept so
+/// webhook tokens never end up in the cache; entries cached with a webhook url
+/// fall back to the default route and lose the url on the next write.
+#[derive(Serialize, Deserialize)]
+struct CommentMessage {
+  #[serde(default = "default_route")]
+  route: String,
+  message_id: String,
+}
+
+/// Route of the default webhook, used when no idea tag has its own.
+const DEFAULT_ROUTE: &str = "default";
+
+fn default_route() -> String {
+  DEFAULT_ROUTE.to_string()
+}
+
 pub struct DiscordHandler {
-  base_url: String,
+  urls: UrlBuilder,
   webhook_url: String,
+  tag_webhook_urls: HashMap<String, String>,
   cache: Cache,
   client: Client,
 }
 
 impl DiscordHandler {
-  pub fn new(base_url: String, webhook_url: String, cache: Cache, client: Client) -> Self {
+  pub fn new(
+    urls: UrlBuilder,
+    webhook_url: String,
+    tag_webhook_urls: HashMap<String, String>,
+    cache: Cache,
+    client: Client,
+  ) -> Self {
     Self {
-      base_url,
+      urls,
       webhook_url,
+      tag_webhook_urls,
       cache,
       client,
     }
   }
 
   pub fn new_from_env(env: &Env) -> Result<DiscordHandler> {
-    let base_url = env.var("PROP_LOT_BASE_URL")?.to_string();
+    let urls = UrlBuilder::new_from_env(env)?;
     let webhook_url = env.secret("PROP_LOT_DISCORD_WEBHOOK_URL")?.to_string();
 
+    // Maps an idea tag to the name of the secret holding its webhook url.
+    let tag_webhook_secrets = env
+      .var("PROP_LOT_DISCORD_TAG_WEBHOOKS")
+      .map(|var| var.to_string())
+      .unwrap_or_default();
+    let tag_webhook_secrets = match tag_webhook_secrets.is_empty() {
+      true => HashMap::new(),
+      false => from_str::<HashMap<String, String>>(&tag_webhook_secrets)?,
+    };
+
+    let mut tag_webhook_urls = HashMap::new();
+    for (tag, secret) in tag_webhook_secrets {
+      match env.secret(&secret) {
+        Ok(url) => {
+          tag_webhook_urls.insert(tag.to_uppercase(), url.to_string());
+        }
+        Err(_) => warn!("Missing webhook secret {} for tag {}", secret, tag),
+      }
+    }
+
     let cache = Cache::new_from_env(env);
     let client = Client::new();
 
-    Ok(Self::new(base_url, webhook_url, cache, client))
+    Ok(Self::new(
+      urls,
+      webhook_url,
+      tag_webhook_urls,
+      cache,
+      client,
+    ))
   }
 
-  async fn execute_webhook(&self, embed: Value) -> Result<()> {
-    let msg_json = json!({
+  /// Returns the first idea tag that has its own webhook, falling back to
+  /// the default route.
+  fn webhook_route_for(&self, idea: &Idea) -> String {
+    idea
+      .tags()
+      .iter()
+      .find(|&tag| self.tag_webhook_urls.contains_key(tag))
+      .cloned()
+      .unwrap_or_else(|| DEFAULT_ROUTE.to_string())
+  }
+
+  /// Returns the webhook of a route, falling back to the default webhook.
+  fn webhook_url_of(&self, route: &str) -> &str {
+    self
+      .tag_webhook_urls
+      .get(route)
+      .unwrap_or(&self.webhook_url)
+  }
+
+  fn webhook_url_for(&self, idea: &Idea) -> &str {
+    self.webhook_url_of(&self.webhook_route_for(idea))
+  }
+
+  fn build_message(&self, embed: Value) -> Value {
+    json!({
       "username": "Raven",
       "avatar_url": "https://res.cloudinary.com/nekofar/image/upload/b_rgb:FFB911/ln_raven.jpg",
       "embeds": [embed]
-    });
+    })
+  }
+
+  async fn execute_webhook(&self, webhook_url: &str, embed: Value) -> Result<()> {
+    let msg_json = self.build_message(embed);
 
     self
       .client
-      .post(&self.webhook_url)
+      .post(webhook_url)
       .header(header::CONTENT_TYPE, "application/json")
       .body(msg_json.to_string())
       .send()
@@ -59,6 +142,157 @@ impl DiscordHandler {
 
     Ok(())
   }
+
+  /// Executes the webhook and returns the id of the created message.
+  async fn execute_webhook_with_id(&self, webhook_url: &str, embed: Value) -> Result<String> {
+    let msg_json = self.build_message(embed);
+
+    let mut url = Url::parse(webhook_url).map_err(|e| Error::from(e.to_string()))?;
+    url.query_pairs_mut().append_pair("wait", "true");
+
+    let response = self
+      .client
+      .post(url)
+      .header(header::CONTENT_TYPE, "application/json")
+      .body(msg_json.to_string())
+      .send()
+      .await
+      .map_err(|e| {
+        error!("Failed to execute webhook: {}", e);
+        Error::from(format!("Failed to execute webhook: {}", e))
+      })?;
+
+    if !response.status().is_success() {
+      error!("Failed to execute webhook: {}", response.status());
+      return Err(Error::from(format!(
+        "Failed to execute webhook: {}",
+        response.status()
+      )));
+    }
+
+    let response_body = response.json::<Value>().await.map_err(|e| {
+      error!("Failed to parse webhook response: {}", e);
+      Error::from(format!("Failed to parse webhook response: {}", e))
+    })?;
+
+    let message_id = response_body["id"]
+      .as_str()
+      .ok_or("Failed to get message id")?;
+
+    Ok(message_id.to_string())
+  }
+
+  async fn edit_webhook_message(
+    &self,
+    webhook_url: &str,
+    message_id: &str,
+    embed: Value,
+  ) -> Result<()> {
+    let msg_json = self.build_message(embed);
+
+    let mut url = Url::parse(webhook_url).map_err(|e| Error::from(e.to_string()))?;
+    url
+      .path_segments_mut()
+      .map_err(|_| Error::from("Webhook url cannot have a path"))?
+      .pop_if_empty()
+      .extend(["messages", message_id]);
+
+    let response = self
+      .client
+      .patch(url)
+      .header(header::CONTENT_TYPE, "application/json")
+      .body(msg_json.to_string())
+      .send()
+      .await
+      .map_err(|e| {
+        error!("Failed to edit webhook message: {}", e);
+        Error::from(format!("Failed to edit webhook message: {}", e))
+      })?;
+
+    if !response.status().is_success() {
+      error!("Failed to edit webhook message: {}", response.status());
+      return Err(Error::from(format!(
+        "Failed to edit webhook message: {}",
+        response.status()
+      )));
+    }
+
+    Ok(())
+  }
+
+  async fn execute_vote_webhook(&self, vote: &Vote, changed: bool) -> Result<()> {
+    let ideas = self
+      .cache
+      .get::<Vec<Idea>>("prop_lot:ideas")
+      .await?
+      .unwrap_or_default();
+
+    let idea = ideas
+      .iter()
+      .find(|&a| a.id == vote.idea_id)
+      .ok_or("Idea not found in the funding list.")?;
+
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let wallet = get_domain_name(&vote.voter_id)
+      .await
+      .unwrap_or(get_short_address(&vote.voter_id));
+    let explorer = get_explorer_address(&vote.voter_id);
+    let url = self.urls.idea_url(idea.id);
+
+    let description = format!(
+      "{} has {} {} “{}” proposal with {} votes.\n\n{}",
+      wallet,
+      match changed {
+        true => "changed their vote to",
+        false => "voted",
+      },
+      vote.direction_label(),
+      idea.title,
+      vote.voter_weight,
+      idea.format_stats()
+    );
+
+    let embed = json!({
+      "title": match changed {
+        true => "Changed Prop Lot Proposal Vote",
+        false => "New Prop Lot Proposal Vote",
+      },
+      "description": description,
+      "url": url,
+      "color": 0xFFB911,
+      "footer": {"text": date},
+      "author": {
+          "name": wallet,
+          "url": explorer,
+      }
+    });
+
+    self
+      .execute_webhook(self.webhook_url_for(idea), embed)
+      .await
+  }
+
+  async fn execute_idea_webhook(
+    &self,
+    idea: &Idea,
+    title: &str,
+    description: String,
+  ) -> Result<()> {
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let url = self.urls.idea_url(idea.id);
+
+    let embed = json!({
+      "title": title,
+      "description": description,
+      "url": url,
+      "color": 0xFFB911,
+      "footer": {"text": date}
+    });
+
+    self
+      .execute_webhook(self.webhook_url_for(idea), embed)
+      .await
+  }
 }
 
 #[async_trait(? Send)]
@@ -67,7 +301,7 @@ impl Handler for DiscordHandler {
     info!("Handling new idea: {}", idea.title);
 
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
-    let url = format!("{}/idea/{}", self.base_url, idea.id);
+    let url = self.urls.idea_url(idea.id);
     let wallet = get_domain_name(&idea.creator_id)
       .await
       .unwrap_or(get_short_address(&idea.creator_id));
@@ -77,7 +311,7 @@ impl Handler for DiscordHandler {
       wallet, idea.title
     );
 
-    let embed = json!({
+    let mut embed = json!({
       "title": "New Prop Lot Proposal",
       "description": description,
       "url": url,
@@ -89,58 +323,148 @@ impl Handler for DiscordHandler {
       }
     });
 
-    self.execute_webhook(embed).await?;
+    if !idea.tags().is_empty() {
+      embed["fields"] = json!([{
+        "name": "Tags",
+        "value": idea.format_tags(),
+      }]);
+    }
+
+    self
+      .execute_webhook(self.webhook_url_for(idea), embed)
+      .await?;
 
     Ok(())
   }
 
-  async fn handle_new_vote(&self, vote: &Vote) -> Result<()> {
-    info!("Handling new vote from address: {}", vote.voter_id);
+  async fn handle_closed_idea(&self, idea: &Idea) -> Result<()> {
+    info!("Handling closed idea: {}", idea.title);
 
-    let ideas = self
-      .cache
-      .get::<Vec<Idea>>("prop_lot:ideas")
-      .await?
-      .unwrap();
+    let description = format!(
+      "“{}” proposal has been closed on Prop Lot.\n\n{}",
+      idea.title,
+      idea.format_stats()
+    );
 
-    let idea = ideas
-      .iter()
-      .find(|&a| a.id == vote.idea_id)
-      .unwrap()
-      .clone();
+    self
+      .execute_idea_webhook(idea, "Prop Lot Proposal Closed", description)
+      .await
+  }
 
-    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
-    let wallet = get_domain_name(&vote.voter_id)
+  async fn handle_archived_idea(&self, idea: &Idea) -> Result<()> {
+    info!("Handling archived idea: {}", idea.title);
+
+    let description = format!(
+      "“{}” proposal has been archived on Prop Lot.\n\n{}",
+      idea.title,
+      idea.format_stats()
+    );
+
+    self
+      .execute_idea_webhook(idea, "Prop Lot Proposal Archived", description)
       .await
-      .unwrap_or(get_short_address(&vote.voter_id));
-    let explorer = get_explorer_address(&vote.voter_id);
-    let url = format!("{}/idea/{}", self.base_url, idea.id);
+  }
+
+  async fn handle_consensus_idea(&self, idea: &Idea, threshold: f64) -> Result<()> {
+    info!("Handling consensus idea: {}", idea.title);
 
     let description = format!(
-      "{} has voted {} “{}” proposal.",
-      wallet,
-      match vote.direction {
-        1 => "for",
-        _ => "against",
-      },
-      idea.title
+      "“{}” proposal has reached {}% consensus on Prop Lot.\n\n{}",
+      idea.title,
+      threshold,
+      idea.format_stats()
+    );
+
+    self
+      .execute_idea_webhook(idea, "Prop Lot Proposal Reached Consensus", description)
+      .await
+  }
+
+  async fn handle_proposed_idea(&self, idea: &Idea, proposal: &Proposal, url: &str) -> Result<()> {
+    info!("Handling proposed idea: {}", idea.title);
+
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let description = format!(
+      "Prop Lot idea #{} “{}” is now Proposal #{}: “{}”",
+      idea.id, idea.title, proposal.id, proposal.title
     );
 
     let embed = json!({
-      "title": "New Prop Lot Proposal Vote",
+      "title": format!("Prop Lot Idea #{} Is Now Proposal #{}", idea.id, proposal.id),
       "description": description,
       "url": url,
       "color": 0xFFB911,
       "footer": {"text": date},
-      "author": {
-          "name": wallet,
-          "url": explorer,
+      "fields": [
+        {"name": "Idea", "value": self.urls.idea_url(idea.id), "inline": true},
+        {"name": "Proposal", "value": url, "inline": true}
+      ]
+    });
+
+    self
+      .execute_webhook(self.webhook_url_for(idea), embed)
+      .await
+  }
+
+  async fn handle_weekly_digest(
+    &self,
+    supported: &[TrendingIdea],
+    discussed: &[TrendingIdea],
+  ) -> Result<()> {
+    info!("Handling weekly digest");
+
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let format_lines = |trending: &[TrendingIdea], delta: fn(&TrendingIdea) -> String| {
+      let lines: Vec<_> = trending
+        .iter()
+        .enumerate()
+        .map(|(rank, t)| {
+          format!(
+            "{}. [{}]({}) ({})",
+            rank + 1,
+            t.idea.title,
+            self.urls.idea_url(t.idea.id),
+            delta(t)
+          )
+        })
+        .collect();
+      match lines.is_empty() {
+        true => "No activity this week.".to_string(),
+        false => lines.join("\n"),
       }
+    };
+
+    let embed = json!({
+      "title": "Prop Lot Weekly Digest",
+      "description": "The most active Prop Lot proposals of the past week.",
+      "url": self.urls.base_url(),
+      "color": 0xFFB911,
+      "footer": {"text": date},
+      "fields": [
+        {
+          "name": "Most Supported",
+          "value": format_lines(supported, |t| format!("+{} votes", t.votes)),
+        },
+        {
+          "name": "Most Discussed",
+          "value": format_lines(discussed, |t| format!("+{} comments", t.comments)),
+        }
+      ]
     });
 
-    self.execute_webhook(embed).await?;
+    self.execute_webhook(&self.webhook_url, embed).await
+  }
 
-    Ok(())
+  async fn handle_new_vote(&self, vote: &Vote) -> Result<()> {
+    info!("Handling new vote from address: {}", vote.voter_id);
+
+    self.execute_vote_webhook(vote, false).await
+  }
+
+  async fn handle_changed_vote(&self, vote: &Vote) -> Result<()> {
+    info!("Handling changed vote from address: {}", vote.voter_id);
+
+    self.execute_vote_webhook(vote, true).await
   }
 
   async fn handle_new_comment(&self, comment: &Comment) -> Result<()> {
@@ -157,14 +481,20 @@ impl Handler for DiscordHandler {
       .unwrap()
       .clone();
 
-    let url = format!("{}/idea/{}", self.base_url, idea.id);
+    let url = self.urls.idea_url(idea.id);
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
     let wallet = get_domain_name(&comment.author_id)
       .await
       .unwrap_or(get_short_address(&comment.author_id));
     let explorer = get_explorer_address(&comment.author_id);
 
-    let mut description = format!("{} has commented on “{}” proposal.", wallet, idea.title);
+    let mut description = match comment.parent_id {
+      Some(_) => format!(
+        "{} has replied to a comment on “{}” proposal.",
+        wallet, idea.title
+      ),
+      None => format!("{} has commented on “{}” proposal.", wallet, idea.title),
+    };
     let chars_limit = 320 - 10 - (description.len() + url.len());
     let mut comment_body = comment.clone().body;
     if comment_body.len() > chars_limit {
@@ -185,7 +515,86 @@ impl Handler for DiscordHandler {
       }
     });
 
-    self.execute_webhook(embed).await?;
+    let route = self.webhook_route_for(&idea);
+    let message_id = self
+      .execute_webhook_with_id(self.webhook_url_of(&route), embed)
+      .await?;
+    debug!("Message id: {}", message_id);
+
+    let mut comments_messages = self
+      .cache
+      .get::<HashMap<String, CommentMessage>>("prop_lot:comments:messages")
+      .await?
+      .unwrap_or_default();
+
+    comments_messages.insert(comment.id.to_string(), CommentMessage { route, message_id });
+
+    self
+      .cache
+      .put(
+        "prop_lot:comments:messages",
+        &to_string(&comments_messages)?,
+      )
+      .await;
+    debug!("Finished putting comments messages in cache");
+
+    Ok(())
+  }
+
+  async fn handle_deleted_comment(&self, comment: &Comment) -> Result<()> {
+    info!("Handling deleted comment: {}", comment.id);
+
+    let mut comments_messages = self
+      .cache
+      .get::<HashMap<String, CommentMessage>>("prop_lot:comments:messages")
+      .await?
+      .unwrap_or_default();
+
+    let Some(message) = comments_messages.remove(&comment.id.to_string()) else {
+      debug!("No message found for comment: {}", comment.id);
+      return Ok(());
+    };
+
+    let ideas = self
+      .cache
+      .get::<Vec<Idea>>("prop_lot:ideas")
+      .await?
+      .unwrap_or_default();
+    let idea = ideas
+      .iter()
+      .find(|&a| a.id == comment.idea_id)
+      .ok_or("Idea not found in the funding list.")?;
+
+    let url = self.urls.idea_url(idea.id);
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let description = format!(
+      "This comment on “{}” proposal has been removed.",
+      idea.title
+    );
+
+    let embed = json!({
+      "title": "Prop Lot Proposal Comment Removed",
+      "description": description,
+      "url": url,
+      "color": 0xFFB911,
+      "footer": {"text": date}
+    });
+
+    self
+      .edit_webhook_message(
+        self.webhook_url_of(&message.route),
+        &message.message_id,
+        embed,
+      )
+      .await?;
+
+    self
+      .cache
+      .put(
+        "prop_lot:comments:messages",
+        &to_string(&comments_messages)?,
+      )
+      .await;
 
     Ok(())
   }
@@ -13,13 +13,14 @@ use worker::{Env, Error, Result};
 
 use crate::{
   cache::Cache,
-  prop_lot::{handler::Handler, Comment, Idea, Vote},
+  lil_nouns::Proposal,
+  prop_lot::{handler::Handler, url::UrlBuilder, Comment, Idea, TrendingIdea, Vote},
   utils,
   utils::fname::get_username_by_address,
 };
 
 pub(crate) struct FarcasterHandler {
-  base_url: String,
+  urls: UrlBuilder,
   warpcast_url: String,
   warpcast_bearer_token: String,
   warpcast_channel_key: String,
@@ -31,7 +32,7 @@ pub(crate) struct FarcasterHandler {
 
 impl FarcasterHandler {
   pub fn new(
-    base_url: String,
+    urls: UrlBuilder,
     warpcast_url: String,
     warpcast_bearer_token: String,
     warpcast_channel_key: String,
@@ -41,7 +42,7 @@ impl FarcasterHandler {
     link: Link,
   ) -> Self {
     Self {
-      base_url,
+      urls,
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
@@ -53,7 +54,7 @@ impl FarcasterHandler {
   }
 
   pub fn new_from_env(env: &Env) -> Result<FarcasterHandler> {
-    let base_url = env.var("PROP_LOT_BASE_URL")?.to_string();
+    let urls = UrlBuilder::new_from_env(env)?;
     let warpcast_url = env.var("WARPCAST_API_BASE_URL")?.to_string();
     let warpcast_bearer_token = env.secret("PROP_LOT_WARPCAST_TOKEN")?.to_string();
     let warpcast_channel_key = env.var("PROP_LOT_WARPCAST_CHANNEL")?.to_string();
@@ -64,7 +65,7 @@ impl FarcasterHandler {
     let link = Link::new_from_env(&env);
 
     Ok(Self::new(
-      base_url,
+      urls,
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
@@ -104,6 +105,139 @@ impl FarcasterHandler {
 
     Ok(response)
   }
+
+  async fn cast_vote(&self, vote: &Vote, changed: bool) -> Result<()> {
+    let ideas = self
+      .cache
+      .get::<Vec<Idea>>("prop_lot:ideas")
+      .await?
+      .unwrap_or_default();
+
+    let idea = ideas
+      .iter()
+      .find(|&a| a.id == vote.idea_id)
+      .ok_or("Idea not found in the funding list.")?;
+
+    let ideas_casts = self
+      .cache
+      .get::<HashMap<String, String>>("prop_lot:ideas:casts")
+      .await?
+      .unwrap_or_default();
+
+    let idea_id = idea.id.to_string();
+    let cast_hash = ideas_casts.get(&idea_id).ok_or("Cast hash not found")?;
+
+    let wallet = get_username_by_address(self.farquest_api_key.as_str(), &vote.voter_id).await;
+
+    let description = format!(
+      "{} has {} {} “{}” proposal with {} votes.\n\n{}",
+      wallet,
+      match changed {
+        true => "changed their vote to",
+        false => "voted",
+      },
+      vote.direction_label(),
+      idea.title,
+      vote.voter_weight,
+      idea.format_stats()
+    );
+
+    let request_data = {
+      if cast_hash.is_empty() {
+        json!({
+          "text": description,
+          "channelKey": self.warpcast_channel_key
+        })
+      } else {
+        json!({
+          "text": description,
+          "channelKey": self.warpcast_channel_key,
+          "parent": {
+            "hash": cast_hash,
+          }
+        })
+      }
+    };
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  /// Casts a reply in the idea's thread, or a new cast linking to `url`
+  /// (the idea by default) when the idea was never cast.
+  async fn cast_idea_reply(
+    &self,
+    idea: &Idea,
+    description: String,
+    url: Option<String>,
+  ) -> Result<()> {
+    let ideas_casts = self
+      .cache
+      .get::<HashMap<String, String>>("prop_lot:ideas:casts")
+      .await?
+      .unwrap_or_default();
+
+    let request_data = match ideas_casts.get(&idea.id.to_string()) {
+      Some(cast_hash) if !cast_hash.is_empty() => {
+        let mut request_data = json!({
+          "text": description,
+          "channelKey": self.warpcast_channel_key,
+          "parent": {"hash": cast_hash},
+        });
+        if let Some(url) = url {
+          request_data["embeds"] = json!([url]);
+        }
+        request_data
+      }
+      _ => json!({
+        "text": description,
+        "embeds": [url.unwrap_or_else(|| self.urls.idea_url(idea.id))],
+        "channelKey": self.warpcast_channel_key,
+      }),
+    };
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn delete_cast(&self, cast_hash: &str) -> Result<()> {
+    let url = format!("{}/casts", self.warpcast_url);
+    let token = format!("Bearer {}", self.warpcast_bearer_token);
+    let mut headers = HeaderMap::new();
+
+    let parsed_token =
+      HeaderValue::from_str(&token).map_err(|_| Error::from("Error while parsing token"))?;
+
+    headers.insert(AUTHORIZATION, parsed_token);
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    let response = self
+      .client
+      .delete(url)
+      .headers(headers)
+      .json(&json!({"castHash": cast_hash}))
+      .send()
+      .await
+      .map_err(|e| {
+        error!("Failed to delete cast: {}", e);
+        Error::from(format!("Failed to delete cast: {}", e))
+      })?;
+
+    debug!("Response status: {:?}", response.status());
+
+    if !response.status().is_success() {
+      error!("Failed to delete cast: {}", response.status());
+      return Err(Error::from(format!(
+        "Failed to delete cast: {}",
+        response.status()
+      )));
+    }
+
+    Ok(())
+  }
 }
 
 #[async_trait(? Send)]
@@ -113,16 +247,19 @@ impl Handler for FarcasterHandler {
 
     let url = &self
       .link
-      .generate(format!("{}/idea/{}", self.base_url, idea.id))
+      .generate(self.urls.idea_url(idea.id))
       .await
-      .unwrap_or_else(|_| format!("{}/idea/{}", self.base_url, idea.id));
+      .unwrap_or_else(|_| self.urls.idea_url(idea.id));
 
     let wallet = get_username_by_address(self.farquest_api_key.as_str(), &idea.creator_id).await;
 
-    let description = format!(
+    let mut description = format!(
       "{} created a new proposal on Prop Lot: “{}”",
       wallet, idea.title
     );
+    if !idea.tags().is_empty() {
+      description = format!("{}\n\n{}", description, idea.format_tags());
+    }
 
     let request_data = json!({
       "text": description,
@@ -132,7 +269,7 @@ impl Handler for FarcasterHandler {
 
     let response = self.make_http_request(request_data).await.map_err(|e| {
       error!("Failed to make HTTP request: {}", e);
-      return e;
+      e
     })?;
 
     let response_body = response.text().await.map_err(|e| {
@@ -177,64 +314,112 @@ impl Handler for FarcasterHandler {
     Ok(())
   }
 
-  async fn handle_new_vote(&self, vote: &Vote) -> Result<()> {
-    info!("Handling new vote from address: {}", vote.voter_id);
+  async fn handle_closed_idea(&self, idea: &Idea) -> Result<()> {
+    info!("Handling closed idea: {}", idea.title);
 
-    let ideas = self
-      .cache
-      .get::<Vec<Idea>>("prop_lot:ideas")
-      .await?
-      .unwrap_or_default();
+    let description = format!(
+      "“{}” proposal has been closed on Prop Lot.\n\n{}",
+      idea.title,
+      idea.format_stats()
+    );
 
-    let idea = ideas
-      .iter()
-      .find(|&a| a.id == vote.idea_id)
-      .clone()
-      .ok_or("Idea not found in the funding list.")?;
+    self.cast_idea_reply(idea, description, None).await
+  }
 
-    let ideas_casts = self
-      .cache
-      .get::<HashMap<String, String>>("prop_lot:ideas:casts")
-      .await?
-      .unwrap_or_default();
+  async fn handle_archived_idea(&self, idea: &Idea) -> Result<()> {
+    info!("Handling archived idea: {}", idea.title);
 
-    let idea_id = idea.id.to_string();
-    let cast_hash = ideas_casts.get(&idea_id).ok_or("Cast hash not found")?;
+    let description = format!(
+      "“{}” proposal has been archived on Prop Lot.\n\n{}",
+      idea.title,
+      idea.format_stats()
+    );
 
-    let wallet = get_username_by_address(self.farquest_api_key.as_str(), &vote.voter_id).await;
+    self.cast_idea_reply(idea, description, None).await
+  }
+
+  async fn handle_consensus_idea(&self, idea: &Idea, threshold: f64) -> Result<()> {
+    info!("Handling consensus idea: {}", idea.title);
 
     let description = format!(
-      "{} has voted {} “{}” proposal.",
-      wallet,
-      match vote.direction {
-        1 => "for",
-        _ => "against",
-      },
-      idea.title
+      "“{}” proposal has reached {}% consensus on Prop Lot.\n\n{}",
+      idea.title,
+      threshold,
+      idea.format_stats()
     );
 
-    let request_data = {
-      if cast_hash.is_empty() {
-        json!({
-          "text": description,
-          "channelKey": self.warpcast_channel_key
-        })
-      } else {
-        json!({
-          "text": description,
-          "channelKey": self.warpcast_channel_key,
-          "parent": {
-            "hash": cast_hash,
-          }
-        })
+    self.cast_idea_reply(idea, description, None).await
+  }
+
+  async fn handle_proposed_idea(&self, idea: &Idea, proposal: &Proposal, url: &str) -> Result<()> {
+    info!("Handling proposed idea: {}", idea.title);
+
+    let description = format!(
+      "Prop Lot idea #{} “{}” is now Proposal #{}: “{}”",
+      idea.id, idea.title, proposal.id, proposal.title
+    );
+
+    self
+      .cast_idea_reply(idea, description, Some(url.to_string()))
+      .await
+  }
+
+  async fn handle_weekly_digest(
+    &self,
+    supported: &[TrendingIdea],
+    discussed: &[TrendingIdea],
+  ) -> Result<()> {
+    info!("Handling weekly digest");
+
+    let mut description = "Prop Lot weekly digest".to_string();
+    if !supported.is_empty() {
+      let lines: Vec<_> = supported
+        .iter()
+        .enumerate()
+        .map(|(rank, t)| format!("{}. {} (+{} votes)", rank + 1, t.idea.title, t.votes))
+        .collect();
+      description = format!("{}\n\nMost supported:\n{}", description, lines.join("\n"));
+    }
+    if !discussed.is_empty() {
+      let lines: Vec<_> = discussed
+        .iter()
+        .enumerate()
+        .map(|(rank, t)| format!("{}. {} (+{} comments)", rank + 1, t.idea.title, t.comments))
+        .collect();
+      description = format!("{}\n\nMost discussed:\n{}", description, lines.join("\n"));
+    }
+    if description.len() > 1024 {
+      let mut end = 1021;
+      while !description.is_char_boundary(end) {
+        end -= 1;
       }
-    };
+      description.truncate(end);
+      description.push_str("...");
+    }
+
+    let request_data = json!({
+      "text": description,
+      "embeds": [self.urls.base_url()],
+      "channelKey": self.warpcast_channel_key
+    });
 
     self.make_http_request(request_data).await?;
 
     Ok(())
   }
 
+  async fn handle_new_vote(&self, vote: &Vote) -> Result<()> {
+    info!("Handling new vote from address: {}", vote.voter_id);
+
+    self.cast_vote(vote, false).await
+  }
+
+  async fn handle_changed_vote(&self, vote: &Vote) -> Result<()> {
+    info!("Handling changed vote from address: {}", vote.voter_id);
+
+    self.cast_vote(vote, true).await
+  }
+
   async fn handle_new_comment(&self, comment: &Comment) -> Result<()> {
     info!("Handling new comment from address: {}", comment.author_id);
 
@@ -247,7 +432,6 @@ impl Handler for FarcasterHandler {
     let idea = ideas
       .iter()
       .find(|&a| a.id == comment.idea_id)
-      .clone()
       .ok_or("Idea not found in the funding list.")?;
 
     let ideas_casts = self
@@ -256,13 +440,31 @@ impl Handler for FarcasterHandler {
       .await?
       .unwrap_or_default();
 
-    let cast_hash = ideas_casts
-      .get(&idea.id.to_string())
-      .ok_or("Cast hash not found")?;
+    let mut comments_casts = self
+      .cache
+      .get::<HashMap<String, String>>("prop_lot:comments:casts")
+      .await?
+      .unwrap_or_default();
+
+    let parent_cast_hash = comment
+      .parent_id
+      .and_then(|parent_id| comments_casts.get(&parent_id.to_string()));
+    let cast_hash = match parent_cast_hash {
+      Some(cast_hash) => cast_hash,
+      None => ideas_casts
+        .get(&idea.id.to_string())
+        .ok_or("Cast hash not found")?,
+    };
 
     let wallet = get_username_by_address(self.farquest_api_key.as_str(), &comment.author_id).await;
 
-    let mut description = format!("{} has commented on “{}” proposal.", wallet, idea.title);
+    let mut description = match comment.parent_id {
+      Some(_) => format!(
+        "{} has replied to a comment on “{}” proposal.",
+        wallet, idea.title
+      ),
+      None => format!("{} has commented on “{}” proposal.", wallet, idea.title),
+    };
     let chars_limit = 1024 - 10 - description.len();
     let mut comment_body = comment.clone().body.trim().to_string();
     if comment_body.len() > chars_limit {
@@ -277,7 +479,55 @@ impl Handler for FarcasterHandler {
       "parent": {"hash": cast_hash},
     });
 
-    self.make_http_request(request_data).await?;
+    let response = self.make_http_request(request_data).await?;
+
+    let response_body = response.text().await.map_err(|e| {
+      error!("Failed to get text from response: {}", e);
+      Error::from(format!("Failed to get text from response: {}", e))
+    })?;
+
+    let response_body: Value = serde_json::from_str(&response_body).map_err(|e| {
+      error!("Failed to parse JSON: {}", e);
+      Error::from(e)
+    })?;
+
+    let cast_hash = response_body["result"]["cast"]["hash"]
+      .as_str()
+      .ok_or("Failed to get cast hash")?;
+    debug!("Cast hash: {}", cast_hash);
+
+    comments_casts.insert(comment.id.to_string(), cast_hash.to_string());
+
+    self
+      .cache
+      .put("prop_lot:comments:casts", &to_string(&comments_casts)?)
+      .await;
+    debug!("Finished putting comments casts in cache");
+
+    Ok(())
+  }
+
+  async fn handle_deleted_comment(&self, comment: &Comment) -> Result<()> {
+    info!("Handling deleted comment: {}", comment.id);
+
+    let mut comments_casts = self
+      .cache
+      .get::<HashMap<String, String>>("prop_lot:comments:casts")
+      .await?
+      .unwrap_or_default();
+
+    let Some(cast_hash) = comments_casts.remove(&comment.id.to_string()) else {
+      debug!("No cast found for comment: {}", comment.id);
+      return Ok(());
+    };
+
+    self.delete_cast(&cast_hash).await?;
+
+    self
+      .cache
+      .put("prop_lot:comments:casts", &to_string(&comments_casts)?)
+      .await;
+    debug!("Finished putting comments casts in cache");
 
     Ok(())
   }
@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 use worker::Result;
 
-use crate::prop_lot::{Comment, Idea, Vote};
+use crate::{
+  lil_nouns::Proposal,
+  prop_lot::{Comment, Idea, TrendingIdea, Vote},
+};
 
 pub(crate) mod discord;
 pub(crate) mod farcaster;
@@ -9,6 +12,17 @@ pub(crate) mod farcaster;
 #[async_trait(? Send)]
 pub trait Handler {
   async fn handle_new_idea(&self, idea: &Idea) -> Result<()>;
+  async fn handle_closed_idea(&self, idea: &Idea) -> Result<()>;
+  async fn handle_archived_idea(&self, idea: &Idea) -> Result<()>;
+  async fn handle_consensus_idea(&self, idea: &Idea, threshold: f64) -> Result<()>;
+  async fn handle_proposed_idea(&self, idea: &Idea, proposal: &Proposal, url: &str) -> Result<()>;
+  async fn handle_weekly_digest(
+    &self,
+    supported: &[TrendingIdea],
+    discussed: &[TrendingIdea],
+  ) -> Result<()>;
   async fn handle_new_vote(&self, vote: &Vote) -> Result<()>;
+  async fn handle_changed_vote(&self, vote: &Vote) -> Result<()>;
   async fn handle_new_comment(&self, comment: &Comment) -> Result<()>;
+  async fn handle_deleted_comment(&self, comment: &Comment) -> Result<()>;
 }
@@ -1,17 +1,37 @@
+use std::{
+  cmp::Reverse,
+  collections::{HashMap, HashSet},
+};
+
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use worker::{Env, Result};
 
 use crate::{
   cache::Cache,
+  lil_nouns::{
+    fetcher::GraphQLFetcher as ProposalFetcher,
+    url::UrlBuilder as ProposalUrlBuilder,
+    Proposal,
+  },
   prop_lot::{
     fetcher::GraphQLFetcher,
     handler::{discord::DiscordHandler, farcaster::FarcasterHandler, Handler},
+    url::UrlBuilder,
   },
 };
 
 pub(crate) mod fetcher;
 pub(crate) mod handler;
+pub(crate) mod url;
+
+const DIGEST_SIZE: usize = 5;
+
+/// Minimum title similarity for matching a proposal to an idea.
+const TITLE_SIMILARITY: f64 = 0.6;
+
+/// Minimum title similarity when the proposal and idea share a creator.
+const CREATOR_TITLE_SIMILARITY: f64 = 0.3;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Idea {
@@ -19,6 +39,66 @@ pub struct Idea {
   pub title: String,
   pub tldr: String,
   pub creator_id: String,
+  #[serde(default)]
+  pub votecount: isize,
+  #[serde(default)]
+  pub consensus: Option<f64>,
+  #[serde(default)]
+  pub closed: bool,
+  /// Unset on ideas cached before tags and closure were tracked.
+  #[serde(default)]
+  pub tags: Option<Vec<String>>,
+}
+
+impl Idea {
+  pub fn consensus_percent(&self) -> Option<f64> {
+    self.consensus.map(|consensus| consensus * 100.0)
+  }
+
+  pub fn tags(&self) -> &[String] {
+    self.tags.as_deref().unwrap_or_default()
+  }
+
+  pub fn is_archived(&self) -> bool {
+    self.tags().iter().any(|tag| tag == "ARCHIVED")
+  }
+
+  pub fn format_tags(&self) -> String {
+    self
+      .tags()
+      .iter()
+      .map(|tag| format!("#{}", tag.to_lowercase()))
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+
+  /// Returns the share of distinct words the idea title has in common with
+  /// the given title, from 0 to 1.
+  pub fn title_similarity(&self, title: &str) -> f64 {
+    let words = |text: &str| -> HashSet<String> {
+      text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() > 2)
+        .map(|word| word.to_lowercase())
+        .collect()
+    };
+
+    let idea_words = words(&self.title);
+    let title_words = words(title);
+    let union = idea_words.union(&title_words).count();
+    if union == 0 {
+      return 0.0;
+    }
+
+    idea_words.intersection(&title_words).count() as f64 / union as f64
+  }
+
+  pub fn format_stats(&self) -> String {
+    match self.consensus_percent() {
+      Some(consensus) => format!("Votes: {} · Consensus: {:.0}%", self.votecount, consensus),
+      None => format!("Votes: {}", self.votecount),
+    }
+  }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -30,32 +110,128 @@ pub struct Vote {
   pub voter_weight: isize,
 }
 
+impl Vote {
+  pub fn direction_label(&self) -> &str {
+    match self.direction {
+      1 => "for",
+      _ => "against",
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Comment {
   pub id: isize,
   pub idea_id: isize,
+  #[serde(default)]
+  pub parent_id: Option<isize>,
   pub author_id: String,
   pub body: String,
+  #[serde(default)]
+  pub deleted: bool,
+}
+
+/// Vote count and comment count of an idea at the time of the last digest.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct IdeaActivity {
+  pub votecount: isize,
+  pub comments: isize,
+}
+
+impl IdeaActivity {
+  fn collect(ideas: &[Idea], comments: &[Comment]) -> HashMap<String, IdeaActivity> {
+    ideas
+      .iter()
+      .map(|idea| {
+        let activity = IdeaActivity {
+          votecount: idea.votecount,
+          comments: comments
+            .iter()
+            .filter(|comment| comment.idea_id == idea.id && !comment.deleted)
+            .count() as isize,
+        };
+        (idea.id.to_string(), activity)
+      })
+      .collect()
+  }
+}
+
+/// An idea together with the votes and comments it gained since the last
+/// digest.
+#[derive(Clone)]
+pub struct TrendingIdea {
+  pub idea: Idea,
+  pub votes: isize,
+  pub comments: isize,
+}
+
+/// Returns the idea a proposal grew out of, either the idea its description
+/// links to or the one with the most similar title.
+fn match_idea<'a>(
+  ideas: &'a [Idea],
+  proposal: &Proposal,
+  linked_id: Option<isize>,
+) -> Option<&'a Idea> {
+  let linked_idea = linked_id.and_then(|idea_id| ideas.iter().find(|idea| idea.id == idea_id));
+  if linked_idea.is_some() {
+    return linked_idea;
+  }
+
+  ideas
+    .iter()
+    .filter_map(|idea| {
+      let similarity = idea.title_similarity(&proposal.title);
+      let threshold = match idea.creator_id.eq_ignore_ascii_case(&proposal.proposer) {
+        true => CREATOR_TITLE_SIMILARITY,
+        false => TITLE_SIMILARITY,
+      };
+      (similarity >= threshold).then_some((idea, similarity))
+    })
+    .max_by(|a, b| a.1.total_cmp(&b.1))
+    .map(|(idea, _)| idea)
 }
 
 pub struct PropLot {
   cache: Cache,
   fetcher: GraphQLFetcher,
+  /// Set when ideas are matched to Lil Nouns proposals, which needs the
+  /// `LIL_NOUNS_GRAPHQL_URL` and `LIL_NOUNS_BASE_URL` variables.
+  proposals: Option<(ProposalFetcher, ProposalUrlBuilder)>,
+  urls: UrlBuilder,
   handlers: Vec<Box<dyn Handler>>,
+  consensus_threshold: Option<f64>,
 }
 
 impl PropLot {
-  pub fn new(cache: Cache, fetcher: GraphQLFetcher, handlers: Vec<Box<dyn Handler>>) -> Self {
+  pub fn new(
+    cache: Cache,
+    fetcher: GraphQLFetcher,
+    proposals: Option<(ProposalFetcher, ProposalUrlBuilder)>,
+    urls: UrlBuilder,
+    handlers: Vec<Box<dyn Handler>>,
+    consensus_threshold: Option<f64>,
+  ) -> Self {
     Self {
       cache,
       fetcher,
+      proposals,
+      urls,
       handlers,
+      consensus_threshold,
     }
   }
 
   pub fn new_from_env(env: &Env) -> Result<Self> {
     let cache = Cache::new_from_env(env);
     let fetcher = GraphQLFetcher::new_from_env(env)?;
+    let proposals = match env.var("PROP_LOT_PROPOSALS_ENABLED")?.to_string() == "true" {
+      true => Some((
+        ProposalFetcher::new_from_env(env)?,
+        ProposalUrlBuilder::new_from_env(env)?,
+      )),
+      false => None,
+    };
+    let urls = UrlBuilder::new_from_env(env)?;
     let mut handlers = vec![];
 
     if env.var("PROP_LOT_DISCORD_ENABLED")?.to_string() == "true" {
@@ -68,7 +244,20 @@ impl PropLot {
       handlers.push(farcaster_handler);
     }
 
-    Ok(Self::new(cache, fetcher, handlers))
+    let consensus_threshold = env
+      .var("PROP_LOT_CONSENSUS_THRESHOLD")?
+      .to_string()
+      .parse::<f64>()
+      .ok();
+
+    Ok(Self::new(
+      cache,
+      fetcher,
+      proposals,
+      urls,
+      handlers,
+      consensus_threshold,
+    ))
   }
 
   pub async fn setup(&self) {
@@ -104,9 +293,170 @@ impl PropLot {
       }
     }
 
+    if let Some((proposal_fetcher, _)) = &self.proposals {
+      if !self.cache.has("prop_lot:proposals").await {
+        if let Some(proposals) = proposal_fetcher.fetch_proposals().await {
+          info!("Fetched {:?} proposals.", proposals.len());
+          debug!("Putting fetched proposal ids into cache.");
+          let ids: Vec<_> = proposals.iter().map(|proposal| proposal.id).collect();
+          self.cache.put("prop_lot:proposals", &ids).await;
+        } else {
+          warn!("Failed to fetch proposals");
+        }
+      }
+    }
+
+    if !self.cache.has("prop_lot:digest:activity").await {
+      if let (Ok(Some(ideas)), Ok(Some(comments))) = (
+        self.cache.get::<Vec<Idea>>("prop_lot:ideas").await,
+        self.cache.get::<Vec<Comment>>("prop_lot:comments").await,
+      ) {
+        debug!("Putting ideas activity into cache.");
+        let activity = IdeaActivity::collect(&ideas, &comments);
+        self.cache.put("prop_lot:digest:activity", &activity).await;
+      }
+    }
+
     debug!("Setup function finished.");
   }
 
+  async fn check_new_proposals(&self) -> Result<()> {
+    let Some((proposal_fetcher, proposal_urls)) = &self.proposals else {
+      return Ok(());
+    };
+    let Some(proposals) = proposal_fetcher.fetch_proposals_with_descriptions().await else {
+      warn!("Failed to fetch proposals");
+      return Ok(());
+    };
+    let Some(mut known_ids) = self.cache.get::<Vec<usize>>("prop_lot:proposals").await? else {
+      return Ok(());
+    };
+    let mut linked_ids = self
+      .cache
+      .get::<Vec<isize>>("prop_lot:ideas:proposals")
+      .await?
+      .unwrap_or_default();
+    let ideas = self
+      .cache
+      .get::<Vec<Idea>>("prop_lot:ideas")
+      .await?
+      .unwrap_or_default();
+
+    let new_proposals: Vec<_> = proposals
+      .iter()
+      .filter(|(proposal, _)| !known_ids.contains(&proposal.id))
+      .collect();
+
+    debug!("Found {:?} new proposals.", new_proposals.len());
+
+    if new_proposals.is_empty() {
+      return Ok(());
+    }
+
+    for (proposal, description) in new_proposals {
+      let unlinked_ideas: Vec<_> = ideas
+        .iter()
+        .filter(|idea| !linked_ids.contains(&idea.id))
+        .cloned()
+        .collect();
+      let linked_id = self.urls.find_idea_id(description);
+
+      if let Some(idea) = match_idea(&unlinked_ideas, proposal, linked_id) {
+        info!(
+          "Handling a proposed idea... ({:?} -> {:?})",
+          idea.id, proposal.id
+        );
+        let url = proposal_urls.proposal_url(proposal.id);
+        for handler in &self.handlers {
+          if let Err(err) = handler.handle_proposed_idea(idea, proposal, &url).await {
+            error!("Failed to handle proposed idea: {:?}", err);
+          } else {
+            debug!("Successfully handled proposed idea: {:?}", idea.id);
+          }
+        }
+        linked_ids.push(idea.id);
+      }
+      known_ids.push(proposal.id);
+    }
+
+    self.cache.put("prop_lot:proposals", &known_ids).await;
+    self
+      .cache
+      .put("prop_lot:ideas:proposals", &linked_ids)
+      .await;
+
+    Ok(())
+  }
+
+  pub async fn post_digest(&self) -> Result<()> {
+    self.setup().await;
+
+    debug!("Digest function started.");
+
+    let Some(ideas) = self.fetcher.fetch_trending_ideas().await else {
+      warn!("Failed to fetch ideas");
+      return Ok(());
+    };
+    let comments = self
+      .cache
+      .get::<Vec<Comment>>("prop_lot:comments")
+      .await?
+      .unwrap_or_default();
+    let last_activity = self
+      .cache
+      .get::<HashMap<String, IdeaActivity>>("prop_lot:digest:activity")
+      .await?
+      .unwrap_or_default();
+
+    let activity = IdeaActivity::collect(&ideas, &comments);
+    let trending: Vec<_> = ideas
+      .iter()
+      .filter(|idea| !idea.closed)
+      .map(|idea| {
+        let id = idea.id.to_string();
+        let current = activity.get(&id).cloned().unwrap_or_default();
+        let last = last_activity.get(&id).cloned().unwrap_or_default();
+        TrendingIdea {
+          idea: idea.clone(),
+          votes: current.votecount - last.votecount,
+          comments: current.comments - last.comments,
+        }
+      })
+      .collect();
+
+    // Sorting is stable, so ties keep the vote ordering of the fetch.
+    let mut supported: Vec<_> = trending.iter().filter(|t| t.votes > 0).cloned().collect();
+    supported.sort_by_key(|t| Reverse(t.votes));
+    supported.truncate(DIGEST_SIZE);
+
+    let mut discussed: Vec<_> = trending
+      .iter()
+      .filter(|t| t.comments > 0)
+      .cloned()
+      .collect();
+    discussed.sort_by_key(|t| Reverse(t.comments));
+    discussed.truncate(DIGEST_SIZE);
+
+    if supported.is_empty() && discussed.is_empty() {
+      info!("No trending ideas this week.");
+    } else {
+      info!("Handling weekly digest...");
+      for handler in &self.handlers {
+        if let Err(err) = handler.handle_weekly_digest(&supported, &discussed).await {
+          error!("Failed to handle weekly digest: {:?}", err);
+        } else {
+          debug!("Successfully handled weekly digest");
+        }
+      }
+    }
+
+    self.cache.put("prop_lot:digest:activity", &activity).await;
+
+    debug!("Digest function finished.");
+
+    Ok(())
+  }
+
   pub async fn start(&self) -> Result<()> {
     self.setup().await;
 
@@ -116,6 +466,7 @@ impl PropLot {
       debug!("Fetched {:?} ideas.", ideas.len());
 
       let mut new_ideas = Vec::new();
+      let mut changed_ideas = false;
 
       if let Some(old_ideas) = self.cache.get::<Vec<Idea>>("prop_lot:ideas").await? {
         let old_ids: Vec<_> = old_ideas.iter().map(|idea| &idea.id).collect();
@@ -137,9 +488,64 @@ impl PropLot {
             }
           }
         }
+
+        for idea in &ideas {
+          let Some(old_idea) = old_ideas.iter().find(|old_idea| old_idea.id == idea.id) else {
+            continue;
+          };
+
+          // Ideas cached before closure, tags and consensus were tracked only
+          // get their new fields stored, without announcing them.
+          let tracked = old_idea.tags.is_some();
+
+          if tracked && !old_idea.closed && idea.closed {
+            info!("Handling a closed idea... ({:?})", idea.id);
+            for handler in &self.handlers {
+              if let Err(err) = handler.handle_closed_idea(idea).await {
+                error!("Failed to handle closed idea: {:?}", err);
+              } else {
+                debug!("Successfully handled closed idea: {:?}", idea.id);
+              }
+            }
+          }
+
+          if tracked && !old_idea.is_archived() && idea.is_archived() {
+            info!("Handling an archived idea... ({:?})", idea.id);
+            for handler in &self.handlers {
+              if let Err(err) = handler.handle_archived_idea(idea).await {
+                error!("Failed to handle archived idea: {:?}", err);
+              } else {
+                debug!("Successfully handled archived idea: {:?}", idea.id);
+              }
+            }
+          }
+
+          if let Some(threshold) = self.consensus_threshold.filter(|_| tracked) {
+            let reached = |idea: &Idea| {
+              idea
+                .consensus_percent()
+                .is_some_and(|consensus| consensus >= threshold)
+            };
+            if !reached(old_idea) && reached(idea) {
+              info!("Handling a consensus idea... ({:?})", idea.id);
+              for handler in &self.handlers {
+                if let Err(err) = handler.handle_consensus_idea(idea, threshold).await {
+                  error!("Failed to handle consensus idea: {:?}", err);
+                } else {
+                  debug!("Successfully handled consensus idea: {:?}", idea.id);
+                }
+              }
+            }
+          }
+
+          changed_ideas |= old_idea.votecount != idea.votecount
+            || old_idea.consensus != idea.consensus
+            || old_idea.closed != idea.closed
+            || old_idea.tags != idea.tags;
+        }
       }
 
-      if !new_ideas.is_empty() {
+      if !new_ideas.is_empty() || changed_ideas {
         self.cache.put("prop_lot:ideas", &ideas).await;
         info!("Updated ideas in cache");
       }
@@ -151,12 +557,23 @@ impl PropLot {
       debug!("Fetched {:?} votes.", votes.len());
 
       let mut new_votes = Vec::new();
+      let mut changed_votes = Vec::new();
 
       if let Some(old_votes) = self.cache.get::<Vec<Vote>>("prop_lot:votes").await? {
-        let old_ids: Vec<_> = old_votes.iter().map(|vote| &vote.id).collect();
+        let find_old_vote = |vote: &Vote| {
+          old_votes
+            .iter()
+            .find(|old_vote| old_vote.idea_id == vote.idea_id && old_vote.voter_id == vote.voter_id)
+        };
+
         new_votes = votes
           .iter()
-          .filter(|vote| !old_ids.contains(&&vote.id))
+          .filter(|vote| find_old_vote(vote).is_none())
+          .cloned()
+          .collect();
+        changed_votes = votes
+          .iter()
+          .filter(|vote| find_old_vote(vote).is_some_and(|old| old.direction != vote.direction))
           .cloned()
           .collect();
 
@@ -172,9 +589,22 @@ impl PropLot {
             }
           }
         }
+
+        debug!("Found {:?} changed votes.", changed_votes.len());
+
+        for vote in &changed_votes {
+          info!("Handling a changed vote...");
+          for handler in &self.handlers {
+            if let Err(err) = handler.handle_changed_vote(vote).await {
+              error!("Failed to handle changed vote: {:?}", err);
+            } else {
+              debug!("Successfully handled changed vote: {:?}", vote.id);
+            }
+          }
+        }
       }
 
-      if !new_votes.is_empty() {
+      if !new_votes.is_empty() || !changed_votes.is_empty() {
         self.cache.put("prop_lot:votes", &votes).await;
         info!("Updated votes in cache");
       }
@@ -186,12 +616,13 @@ impl PropLot {
       debug!("Fetched {:?} comments.", comments.len());
 
       let mut new_comments = Vec::new();
+      let mut deleted_comments = Vec::new();
 
       if let Some(old_comments) = self.cache.get::<Vec<Comment>>("prop_lot:comments").await? {
         let old_ids: Vec<_> = old_comments.iter().map(|comment| &comment.id).collect();
         new_comments = comments
           .iter()
-          .filter(|comment| !old_ids.contains(&&comment.id))
+          .filter(|comment| !old_ids.contains(&&comment.id) && !comment.deleted)
           .cloned()
           .collect();
 
@@ -207,9 +638,33 @@ impl PropLot {
             }
           }
         }
+
+        deleted_comments = comments
+          .iter()
+          .filter(|comment| {
+            comment.deleted
+              && old_comments
+                .iter()
+                .any(|old_comment| old_comment.id == comment.id && !old_comment.deleted)
+          })
+          .cloned()
+          .collect();
+
+        debug!("Found {:?} deleted comments.", deleted_comments.len());
+
+        for comment in &deleted_comments {
+          info!("Handling a deleted comment...");
+          for handler in &self.handlers {
+            if let Err(err) = handler.handle_deleted_comment(comment).await {
+              error!("Failed to handle deleted comment: {:?}", err);
+            } else {
+              debug!("Successfully handled deleted comment: {:?}", comment.id);
+            }
+          }
+        }
       }
 
-      if !new_comments.is_empty() {
+      if !new_comments.is_empty() || !deleted_comments.is_empty() {
         self.cache.put("prop_lot:comments", &comments).await;
         info!("Updated comments in cache");
       }
@@ -217,8 +672,86 @@ impl PropLot {
       warn!("Failed to fetch comments");
     }
 
+    self.check_new_proposals().await?;
+
     debug!("Start function finished.");
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn idea(id: isize, title: &str, creator_id: &str) -> Idea {
+    Idea {
+      id,
+      title: title.to_string(),
+      tldr: String::new(),
+      creator_id: creator_id.to_string(),
+      votecount: 0,
+      consensus: None,
+      closed: false,
+      tags: Some(Vec::new()),
+    }
+  }
+
+  fn proposal(title: &str, proposer: &str) -> Proposal {
+    Proposal {
+      id: 1,
+      title: title.to_string(),
+      proposer: proposer.to_string(),
+    }
+  }
+
+  #[test]
+  fn compares_titles_by_shared_words() {
+    let idea = idea(1, "Fund a Lil Nouns Podcast", "0xabc");
+
+    assert_eq!(idea.title_similarity("fund a lil nouns podcast!"), 1.0);
+    assert_eq!(idea.title_similarity("Lil Nouns Podcast"), 0.75);
+    assert_eq!(idea.title_similarity("Something else"), 0.0);
+    assert_eq!(idea.title_similarity(""), 0.0);
+  }
+
+  #[test]
+  fn matches_linked_ideas_first() {
+    let ideas = vec![
+      idea(1, "Lil Nouns Podcast", "0xabc"),
+      idea(2, "Art", "0xdef"),
+    ];
+
+    let matched = match_idea(&ideas, &proposal("Lil Nouns Podcast", "0xabc"), Some(2));
+    assert_eq!(matched.map(|idea| idea.id), Some(2));
+  }
+
+  #[test]
+  fn matches_ideas_by_title() {
+    let ideas = vec![
+      idea(1, "Fund a Lil Nouns Podcast", "0xabc"),
+      idea(2, "Lil Nouns Podcast Season Two", "0xdef"),
+    ];
+
+    let matched = match_idea(
+      &ideas,
+      &proposal("Lil Nouns Podcast Season Two", "0x123"),
+      None,
+    );
+    assert_eq!(matched.map(|idea| idea.id), Some(2));
+
+    let matched = match_idea(&ideas, &proposal("A Podcast About Nouns", "0x123"), None);
+    assert_eq!(matched.map(|idea| idea.id), None);
+  }
+
+  #[test]
+  fn lowers_the_title_threshold_for_the_same_creator() {
+    let ideas = vec![idea(1, "Fund a Lil Nouns Podcast", "0xABC")];
+
+    let matched = match_idea(&ideas, &proposal("Nouns Podcast Budget", "0xabc"), None);
+    assert_eq!(matched.map(|idea| idea.id), Some(1));
+
+    let matched = match_idea(&ideas, &proposal("Nouns Podcast Budget", "0x123"), None);
+    assert_eq!(matched.map(|idea| idea.id), None);
+  }
+}
@@ -0,0 +1,67 @@
+use worker::{Env, Result};
+
+pub struct UrlBuilder {
+  base_url: String,
+}
+
+impl UrlBuilder {
+  pub fn new(base_url: String) -> Self {
+    Self {
+      base_url: base_url.trim_end_matches('/').to_string(),
+    }
+  }
+
+  pub fn new_from_env(env: &Env) -> Result<UrlBuilder> {
+    let base_url = env.var("PROP_LOT_BASE_URL")?.to_string();
+
+    Ok(Self::new(base_url))
+  }
+
+  pub fn base_url(&self) -> &str {
+    &self.base_url
+  }
+
+  pub fn idea_url(&self, idea_id: isize) -> String {
+    format!("{}/idea/{}", self.base_url, idea_id)
+  }
+
+  /// Finds the first idea link in the given text and returns its id.
+  pub fn find_idea_id(&self, text: &str) -> Option<isize> {
+    let prefix = format!("{}/idea/", self.base_url);
+    text.match_indices(&prefix).find_map(|(index, _)| {
+      let digits: String = text[index + prefix.len()..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+      digits.parse().ok()
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn builds_idea_urls() {
+    let builder = UrlBuilder::new("https://lilnouns.proplot.wtf/".to_string());
+
+    assert_eq!(builder.idea_url(42), "https://lilnouns.proplot.wtf/idea/42");
+  }
+
+  #[test]
+  fn finds_idea_ids_in_text() {
+    let builder = UrlBuilder::new("https://lilnouns.proplot.wtf".to_string());
+
+    assert_eq!(
+      builder.find_idea_id("See [the idea](https://lilnouns.proplot.wtf/idea/17) for context."),
+      Some(17)
+    );
+    assert_eq!(
+      builder
+        .find_idea_id("https://lilnouns.proplot.wtf/idea/ and https://lilnouns.proplot.wtf/idea/8"),
+      Some(8)
+    );
+    assert_eq!(builder.find_idea_id("https://example.com/idea/17"), None);
+  }
+}
@@ -1,8 +1,45 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, time::Duration};
 
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use worker::{Env, Result};
+use log::{debug, warn};
+use reqwest::{header::RETRY_AFTER, Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use worker::{Delay, Env, Error, Result};
+
+/// How many times a request is retried after a rate limit or server error.
+const MAX_RETRIES: u32 = 3;
+
+/// The longest we wait before retrying, whatever `Retry-After` asks for.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum FetchError {
+  /// The request could not be sent or its body could not be read.
+  Request(reqwest::Error),
+  /// Reservoir answered with a non-success status code.
+  Status(StatusCode, String),
+  /// The response body does not match the expected model.
+  Parse(serde_json::Error),
+}
+
+impl fmt::Display for FetchError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FetchError::Request(e) => write!(f, "Reservoir request failed: {}", e),
+      FetchError::Status(status, body) => {
+        write!(f, "Reservoir responded with {}: {}", status, body)
+      }
+      FetchError::Parse(e) => write!(f, "Unexpected Reservoir response: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<FetchError> for Error {
+  fn from(error: FetchError) -> Self {
+    Error::RustError(error.to_string())
+  }
+}
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -10,72 +47,41 @@ pub struct Root {
   pub collections: Vec<Collection>,
 }
 
+/// The parts of a Reservoir collection the bots use. The id and the prices
+/// the bots compare are required, everything only shown to users is optional
+/// so that schema changes elsewhere do not break parsing.
 #[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Collection {
-  pub chain_id: u64,
   pub id: String,
+  #[serde(default)]
   pub slug: String,
-  pub created_at: String,
-  pub updated_at: String,
+  #[serde(default)]
   pub name: String,
-  pub symbol: String,
-  pub contract_deployed_at: Option<String>,
-  pub image: String,
-  pub banner: Option<String>,
-  pub twitter_url: Option<String>,
-  pub discord_url: Option<String>,
-  pub external_url: String,
-  pub twitter_username: Option<String>,
-  pub opensea_verification_status: String,
-  pub description: String,
-  pub metadata_disabled: bool,
-  pub is_spam: bool,
-  pub sample_images: Vec<String>,
-  pub token_count: String,
-  pub on_sale_count: String,
-  pub primary_contract: String,
-  pub token_set_id: String,
-  pub creator: String,
-  pub royalties: Royalties,
-  pub all_royalties: AllRoyalties,
-  pub floor_ask: FloorAsk,
-  pub top_bid: TopBid,
-  pub rank: HashMap<String, Option<u64>>,
-  pub volume: HashMap<String, f64>,
-  pub volume_change: HashMap<String, f64>,
-  pub floor_sale: HashMap<String, f64>,
-  pub floor_sale_change: HashMap<String, f64>,
-  pub collection_bid_supported: bool,
-  pub owner_count: u64,
-  pub contract_kind: String,
-  pub minted_timestamp: u64,
-  pub mint_stages: Vec<String>,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Royalties {
-  pub recipient: Option<String>,
-  pub breakdown: Vec<String>,
-  pub bps: u64,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct AllRoyalties {
-  pub opensea: Vec<String>,
+  #[serde(default)]
+  pub on_sale_count: Option<String>,
+  #[serde(default)]
+  pub floor_ask: Option<FloorAsk>,
+  #[serde(default)]
+  pub top_bid: Option<TopBid>,
+  #[serde(default)]
+  pub volume: HashMap<String, Option<f64>>,
+  #[serde(default)]
+  pub volume_change: HashMap<String, Option<f64>>,
+  #[serde(default)]
+  pub owner_count: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FloorAsk {
   pub id: String,
-  pub source_domain: String,
   pub price: Price,
+  #[serde(default)]
+  pub source_domain: String,
+  #[serde(default)]
   pub maker: String,
-  pub valid_from: u64,
-  pub valid_until: u64,
+  #[serde(default)]
   pub token: Token,
 }
 
@@ -83,59 +89,152 @@ pub struct FloorAsk {
 #[serde(rename_all = "camelCase")]
 pub struct TopBid {
   pub id: String,
-  pub source_domain: String,
   pub price: Price,
+  #[serde(default)]
+  pub source_domain: String,
+  #[serde(default)]
   pub maker: String,
-  pub valid_from: u64,
-  pub valid_until: u64,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Price {
-  pub currency: Currency,
   pub amount: Amount,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Currency {
+pub struct Amount {
+  pub decimal: f64,
+  #[serde(default)]
+  pub usd: Option<f64>,
+}
+
+impl Amount {
+  /// Formats the USD value for display, or "n/a" when Reservoir has none.
+  pub fn format_usd(&self) -> String {
+    self
+      .usd
+      .map(|usd| format!("${:.2}", usd))
+      .unwrap_or("n/a".to_string())
+  }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Token {
   pub contract: String,
-  pub name: String,
-  pub symbol: String,
-  pub decimals: u8,
+  pub token_id: String,
+  pub name: Option<String>,
+  pub image: Option<String>,
+}
+
+impl Token {
+  pub fn display_name(&self) -> String {
+    self
+      .name
+      .clone()
+      .unwrap_or(format!("Lil Noun {}", self.token_id))
+  }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Amount {
-  pub raw: String,
-  pub decimal: f64,
-  pub usd: f64,
-  pub native: f64,
+pub struct SalesRoot {
+  pub sales: Vec<Sale>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Token {
+pub struct Sale {
+  pub sale_id: String,
+  #[serde(default)]
+  pub token: Token,
+  #[serde(default)]
+  pub order_source: Option<String>,
+  #[serde(default)]
+  pub from: String,
+  #[serde(default)]
+  pub to: String,
+  #[serde(default)]
+  pub tx_hash: String,
+  #[serde(default)]
+  pub timestamp: u64,
+  pub price: Price,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AsksRoot {
+  pub orders: Vec<Ask>,
+}
+
+/// An active listing, as returned by the asks endpoint.
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ask {
+  pub id: String,
+  pub price: Price,
+  #[serde(default)]
   pub contract: String,
-  pub token_id: String,
-  pub name: String,
-  pub image: String,
+  #[serde(default)]
+  pub maker: String,
+  #[serde(default)]
+  pub source: Option<Source>,
+  #[serde(default)]
+  pub criteria: Option<Criteria>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Source {
+  pub domain: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Criteria {
+  pub data: CriteriaData,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CriteriaData {
+  pub token: Option<Token>,
+}
+
+impl From<Ask> for FloorAsk {
+  fn from(ask: Ask) -> Self {
+    let mut token = ask
+      .criteria
+      .and_then(|criteria| criteria.data.token)
+      .unwrap_or_default();
+    token.contract = ask.contract;
+
+    FloorAsk {
+      id: ask.id,
+      price: ask.price,
+      source_domain: ask.source.map(|source| source.domain).unwrap_or_default(),
+      maker: ask.maker,
+      token,
+    }
+  }
 }
 
 pub struct RestFetcher {
   api_key: String,
   base_url: String,
   collection: String,
+  client: Client,
 }
 
 impl RestFetcher {
-  pub fn new(api_key: String, base_url: String, collection: String) -> Self {
+  pub fn new(api_key: String, base_url: String, collection: String, client: Client) -> Self {
     Self {
       api_key,
       base_url,
       collection,
+      client,
     }
   }
 
@@ -144,27 +243,108 @@ impl RestFetcher {
     let base_url = env.var("SECOND_MARKET_API_BASE_URL")?.to_string();
     let collection = env.var("SECOND_MARKET_COLLECTION_ADDRESS")?.to_string();
 
-    Ok(Self::new(api_key, base_url, collection))
+    let client = Client::new();
+
+    Ok(Self::new(api_key, base_url, collection, client))
   }
 
-  pub async fn fetch_collections(&self) -> Option<Vec<Collection>> {
-    let endpoint = format!("{}/collections/v7?id={}", self.base_url, self.collection);
+  /// Sends a GET request, retrying with backoff on rate limits and server
+  /// errors, and parses the response body.
+  async fn fetch<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, FetchError> {
+    let url = format!("{}{}", self.base_url, endpoint);
+    let mut attempt = 0;
 
-    let client = Client::new();
+    loop {
+      let response = self
+        .client
+        .get(&url)
+        .header("X-Api-Key", &self.api_key)
+        .send()
+        .await;
+
+      let retry_delay = match &response {
+        Ok(response)
+          if response.status() == StatusCode::TOO_MANY_REQUESTS
+            || response.status().is_server_error() =>
+        {
+          let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs);
+          Some(retry_after.unwrap_or(Duration::from_secs(1 << attempt)))
+        }
+        Ok(_) => None,
+        Err(e) if e.is_timeout() || e.is_connect() => Some(Duration::from_secs(1 << attempt)),
+        Err(_) => None,
+      };
+
+      if let Some(delay) = retry_delay.filter(|_| attempt < MAX_RETRIES) {
+        attempt += 1;
+        warn!(
+          "Retrying Reservoir request to {} in {:?} (attempt {})",
+          endpoint, delay, attempt
+        );
+        Delay::from(delay.min(MAX_RETRY_DELAY)).await;
+        continue;
+      }
+
+      let response = response.map_err(FetchError::Request)?;
+      let status = response.status();
+      let body = response.text().await.map_err(FetchError::Request)?;
+
+      if !status.is_success() {
+        return Err(FetchError::Status(status, body));
+      }
+
+      debug!("Fetched {} ({} bytes)", endpoint, body.len());
+
+      return serde_json::from_str::<T>(&body).map_err(FetchError::Parse);
+    }
+  }
+
+  pub async fn fetch_collections(&self) -> Result<Vec<Collection>, FetchError> {
+    let endpoint = format!("/collections/v7?id={}", self.collection);
+
+    let root = self.fetch::<Root>(&endpoint).await?;
+
+    Ok(root.collections)
+  }
+
+  /// Fetches the latest sales of the collection, most recent first.
+  pub async fn fetch_sales(&self) -> Result<Vec<Sale>, FetchError> {
+    let endpoint = format!(
+      "/sales/v6?collection={}&sortBy=time&sortDirection=desc&limit=50",
+      self.collection
+    );
+
+    let root = self.fetch::<SalesRoot>(&endpoint).await?;
+
+    Ok(root.sales)
+  }
+
+  /// Fetches the sales of the collection made since the given time.
+  pub async fn fetch_sales_since(&self, timestamp: i64) -> Result<Vec<Sale>, FetchError> {
+    let endpoint = format!(
+      "/sales/v6?collection={}&startTimestamp={}&limit=1000",
+      self.collection, timestamp
+    );
+
+    let root = self.fetch::<SalesRoot>(&endpoint).await?;
+
+    Ok(root.sales)
+  }
 
-    let response = client
-      .get(endpoint)
-      .header("X-Api-Key", &self.api_key)
-      .send()
-      .await
-      .unwrap();
+  /// Fetches the latest active listings of the collection, most recent first.
+  pub async fn fetch_asks(&self) -> Result<Vec<Ask>, FetchError> {
+    let endpoint = format!(
+      "/orders/asks/v5?contracts={}&status=active&sortBy=createdAt&limit=50",
+      self.collection
+    );
 
-    let collections = response
-      .json::<Root>()
-      .await
-      .unwrap_or_default()
-      .collections;
+    let root = self.fetch::<AsksRoot>(&endpoint).await?;
 
-    Some(collections)
+    Ok(root.orders)
   }
 }
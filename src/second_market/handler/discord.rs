@@ -6,21 +6,25 @@ use serde_json::{json, Value};
 use worker::{Env, Error, Result};
 
 use crate::{
-  cache::Cache,
-  second_market::{fetcher::Collection, handler::Handler},
+  second_market::{
+    fetcher::{Collection, FloorAsk, Sale, TopBid},
+    handler::Handler,
+    url::{marketplace_collection_url, marketplace_name, marketplace_url},
+    FloorChange,
+    MarketSummary,
+  },
+  utils::{ens::get_domain_name, get_explorer_address, get_short_address},
 };
 
 pub(crate) struct DiscordHandler {
   webhook_url: String,
-  cache: Cache,
   client: Client,
 }
 
 impl DiscordHandler {
-  pub fn new(webhook_url: String, cache: Cache, client: Client) -> Self {
+  pub fn new(webhook_url: String, client: Client) -> Self {
     Self {
       webhook_url,
-      cache,
       client,
     }
   }
@@ -28,10 +32,9 @@ impl DiscordHandler {
   pub fn new_from_env(env: &Env) -> Result<Self> {
     let webhook_url = env.secret("SECOND_MARKET_DISCORD_WEBHOOK_URL")?.to_string();
 
-    let cache = Cache::new_from_env(env);
     let client = Client::new();
 
-    Ok(Self::new(webhook_url, cache, client))
+    Ok(Self::new(webhook_url, client))
   }
 
   async fn execute_webhook(&self, embed: Value) -> Result<()> {
@@ -59,38 +62,216 @@ impl DiscordHandler {
 
 #[async_trait(? Send)]
 impl Handler for DiscordHandler {
-  async fn handle_new_floor(&self, collection: &Collection) -> Result<()> {
-    info!(
-      "Handling new floor: {:?}",
-      collection.floor_ask.price.amount.decimal
-    );
+  async fn handle_new_floor(&self, collection: &Collection, change: &FloorChange) -> Result<()> {
+    let floor_ask = collection
+      .floor_ask
+      .as_ref()
+      .ok_or("Collection has no floor ask")?;
+    info!("Handling new floor: {:?}", floor_ask.price.amount.decimal);
 
-    let old_price = self
-      .cache
-      .get::<f64>("second_market:old_price")
-      .await?
-      .unwrap_or_default();
-    let new_price = collection.floor_ask.price.amount.decimal;
+    let token = &floor_ask.token;
+    let amount = &floor_ask.price.amount;
 
     let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
-    let url = format!("https://pro.opensea.io/collection/{}", collection.slug);
+    let url = marketplace_url(&floor_ask.source_domain, &token.contract, &token.token_id);
+    let seller = get_domain_name(&floor_ask.maker)
+      .await
+      .unwrap_or(get_short_address(&floor_ask.maker));
 
     let description = format!(
       "There has been a change in the floor price on the second market. The new floor price is \
-       now **{}** Ξ, while the previous was **{}** Ξ.",
-      new_price, old_price
+       now **{}** Ξ ({}), while the previous was **{}** Ξ.",
+      amount.decimal,
+      amount.format_usd(),
+      change.old_price
     );
 
-    let embed = json!({
+    let mut embed = json!({
       "title": "New Second Market Floor",
       "description": description,
       "url": url,
       "color": 0x039BE5,
-      "footer": {"text": date}
+      "footer": {"text": date},
+      "author": {
+        "name": seller,
+        "url": get_explorer_address(&floor_ask.maker),
+      },
+      "fields": [
+        {"name": "Token", "value": token.display_name(), "inline": true},
+        {"name": "Marketplace", "value": format!("[{}]({})", marketplace_name(&floor_ask.source_domain), url), "inline": true},
+        {"name": "24h Change", "value": FloorChange::format_percent(change.day), "inline": true},
+        {"name": "7d Change", "value": FloorChange::format_percent(change.week), "inline": true}
+      ]
     });
 
+    if let Some(image) = &token.image {
+      embed["thumbnail"] = json!({"url": image});
+    }
+
     self.execute_webhook(embed).await?;
 
     Ok(())
   }
+
+  async fn handle_new_top_bid(&self, collection: &Collection, old_top_bid: &TopBid) -> Result<()> {
+    let top_bid = collection
+      .top_bid
+      .as_ref()
+      .ok_or("Collection has no top bid")?;
+    info!("Handling new top bid: {:?}", top_bid.price.amount.decimal);
+
+    let amount = &top_bid.price.amount;
+
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let url = marketplace_collection_url(&top_bid.source_domain, &collection.id, &collection.slug);
+    let bidder = get_domain_name(&top_bid.maker)
+      .await
+      .unwrap_or(get_short_address(&top_bid.maker));
+
+    let description = format!(
+      "The top bid on the second market is now **{}** Ξ ({}) on {}, while the previous was **{}** \
+       Ξ.",
+      amount.decimal,
+      amount.format_usd(),
+      marketplace_name(&top_bid.source_domain),
+      old_top_bid.price.amount.decimal
+    );
+
+    let embed = json!({
+      "title": "New Second Market Top Bid",
+      "description": description,
+      "url": url,
+      "color": 0x039BE5,
+      "footer": {"text": date},
+      "author": {
+        "name": bidder,
+        "url": get_explorer_address(&top_bid.maker),
+      }
+    });
+
+    self.execute_webhook(embed).await
+  }
+
+  async fn handle_new_listing(&self, listing: &FloorAsk, old_floor_ask: &FloorAsk) -> Result<()> {
+    info!("Handling new listing: {:?}", listing.id);
+
+    let token = &listing.token;
+    let amount = &listing.price.amount;
+
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let url = marketplace_url(&listing.source_domain, &token.contract, &token.token_id);
+    let seller = get_domain_name(&listing.maker)
+      .await
+      .unwrap_or(get_short_address(&listing.maker));
+
+    let description = format!(
+      "{} was listed for **{}** Ξ ({}) on {}, below the previous floor of **{}** Ξ.",
+      token.display_name(),
+      amount.decimal,
+      amount.format_usd(),
+      marketplace_name(&listing.source_domain),
+      old_floor_ask.price.amount.decimal
+    );
+
+    let mut embed = json!({
+      "title": "New Second Market Listing Below Floor",
+      "description": description,
+      "url": url,
+      "color": 0x039BE5,
+      "footer": {"text": date},
+      "author": {
+        "name": seller,
+        "url": get_explorer_address(&listing.maker),
+      }
+    });
+
+    if let Some(image) = &token.image {
+      embed["image"] = json!({"url": image});
+    }
+
+    self.execute_webhook(embed).await
+  }
+
+  async fn handle_market_summary(
+    &self,
+    collection: &Collection,
+    summary: &MarketSummary,
+  ) -> Result<()> {
+    info!("Handling market summary: {}", collection.name);
+
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let source_domain = collection
+      .floor_ask
+      .as_ref()
+      .map_or("", |floor_ask| floor_ask.source_domain.as_str());
+    let url = marketplace_collection_url(source_domain, &collection.id, &collection.slug);
+    let format_eth = |value: Option<f64>| match value {
+      Some(value) => format!("{:.4} Ξ", value),
+      None => "n/a".to_string(),
+    };
+
+    let description = format!(
+      "Here is the second market recap of the past day.\n\nFloor over the past week: `{}`",
+      summary.sparkline
+    );
+
+    let embed = json!({
+      "title": "Daily Second Market Summary",
+      "description": description,
+      "url": url,
+      "color": 0x039BE5,
+      "footer": {"text": date},
+      "fields": [
+        {"name": "Floor Open", "value": format_eth(summary.floor_open), "inline": true},
+        {"name": "Floor Close", "value": format!("{} ({})", format_eth(Some(summary.floor_close.decimal)), summary.floor_close.format_usd()), "inline": true},
+        {"name": "Sales", "value": summary.sales.to_string(), "inline": true},
+        {"name": "24h Volume", "value": format_eth(summary.volume), "inline": true},
+        {"name": "Volume Change", "value": FloorChange::format_percent(summary.volume_change), "inline": true},
+        {"name": "Owners", "value": summary.owner_count.map_or("n/a".to_string(), |count| count.to_string()), "inline": true}
+      ]
+    });
+
+    self.execute_webhook(embed).await
+  }
+
+  async fn handle_new_sale(&self, sale: &Sale) -> Result<()> {
+    info!("Handling new sale: {}", sale.sale_id);
+
+    let date = Local::now().format("%m/%d/%Y %I:%M %p").to_string();
+    let source = sale.order_source.clone().unwrap_or_default();
+    let url = marketplace_url(&source, &sale.token.contract, &sale.token.token_id);
+    let name = sale.token.display_name();
+    let seller = get_domain_name(&sale.from)
+      .await
+      .unwrap_or(get_short_address(&sale.from));
+    let buyer = get_domain_name(&sale.to)
+      .await
+      .unwrap_or(get_short_address(&sale.to));
+
+    let description = format!(
+      "{} was sold for **{} Ξ** ({}) on {}.",
+      name,
+      sale.price.amount.decimal,
+      sale.price.amount.format_usd(),
+      marketplace_name(&source)
+    );
+
+    let mut embed = json!({
+      "title": "New Second Market Sale",
+      "description": description,
+      "url": url,
+      "color": 0x039BE5,
+      "footer": {"text": date},
+      "fields": [
+        {"name": "Seller", "value": format!("[{}]({})", seller, get_explorer_address(&sale.from)), "inline": true},
+        {"name": "Buyer", "value": format!("[{}]({})", buyer, get_explorer_address(&sale.to)), "inline": true}
+      ]
+    });
+
+    if let Some(image) = &sale.token.image {
+      embed["image"] = json!({"url": image});
+    }
+
+    self.execute_webhook(embed).await
+  }
 }
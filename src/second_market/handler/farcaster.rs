@@ -10,15 +10,21 @@ use serde_json::{json, Value};
 use worker::{Env, Error, Result};
 
 use crate::{
-  cache::Cache,
-  second_market::{fetcher::Collection, handler::Handler},
+  second_market::{
+    fetcher::{Collection, FloorAsk, Sale, TopBid},
+    handler::Handler,
+    url::{marketplace_collection_url, marketplace_name, marketplace_url},
+    FloorChange,
+    MarketSummary,
+  },
+  utils::fname::get_username_by_address,
 };
 
 pub(crate) struct FarcasterHandler {
   warpcast_url: String,
   warpcast_bearer_token: String,
   warpcast_channel_key: String,
-  cache: Cache,
+  farquest_api_key: String,
   client: Client,
 }
 
@@ -27,14 +33,14 @@ impl FarcasterHandler {
     warpcast_url: String,
     warpcast_bearer_token: String,
     warpcast_channel_key: String,
-    cache: Cache,
+    farquest_api_key: String,
     client: Client,
   ) -> Self {
     Self {
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
-      cache,
+      farquest_api_key,
       client,
     }
   }
@@ -43,15 +49,15 @@ impl FarcasterHandler {
     let warpcast_url = env.var("WARPCAST_API_BASE_URL")?.to_string();
     let warpcast_bearer_token = env.secret("SECOND_MARKET_WARPCAST_TOKEN")?.to_string();
     let warpcast_channel_key = env.var("SECOND_MARKET_WARPCAST_CHANNEL")?.to_string();
+    let farquest_api_key = env.secret("FARQUEST_API_KEY")?.to_string();
 
-    let cache = Cache::new_from_env(env);
     let client = Client::new();
 
     Ok(Self::new(
       warpcast_url,
       warpcast_bearer_token,
       warpcast_channel_key,
-      cache,
+      farquest_api_key,
       client,
     ))
   }
@@ -89,41 +95,203 @@ impl FarcasterHandler {
 
 #[async_trait(? Send)]
 impl Handler for FarcasterHandler {
-  async fn handle_new_floor(&self, collection: &Collection) -> Result<()> {
-    info!(
-      "Handling new floor: {}",
-      collection.floor_ask.price.amount.decimal
-    );
-    let now: DateTime<Utc> = Utc::now();
+  async fn handle_new_floor(&self, collection: &Collection, change: &FloorChange) -> Result<()> {
+    let floor_ask = collection
+      .floor_ask
+      .as_ref()
+      .ok_or("Collection has no floor ask")?;
+    info!("Handling new floor: {}", floor_ask.price.amount.decimal);
 
-    let old_price = self
-      .cache
-      .get::<f64>("second_market:old_price")
-      .await?
-      .unwrap_or_default();
-    let new_price = collection.floor_ask.price.amount.decimal;
+    let token = &floor_ask.token;
+    let amount = &floor_ask.price.amount;
 
-    let mut url = format!("https://blur.io/eth/collection/{}", collection.slug);
-    url = format!("{}?{}", url, now.timestamp());
+    let url = marketplace_url(&floor_ask.source_domain, &token.contract, &token.token_id);
+    let seller = get_username_by_address(self.farquest_api_key.as_str(), &floor_ask.maker).await;
 
     let description = format!(
       "There has been a change in the floor price on the second market. The new floor price is \
-       now {} Ξ, while the previous was {} Ξ.",
-      new_price, old_price
+       now {} Ξ ({}), while the previous was {} Ξ.\n\n{} is listed by {} on {}.\n\n24h: {} · 7d: \
+       {}",
+      amount.decimal,
+      amount.format_usd(),
+      change.old_price,
+      token.display_name(),
+      seller,
+      marketplace_name(&floor_ask.source_domain),
+      FloorChange::format_percent(change.day),
+      FloorChange::format_percent(change.week)
     );
 
+    let mut embeds = vec![url];
+    if let Some(image) = &token.image {
+      embeds.push(image.clone());
+    }
+
     let request_data = json!({
       "text": description,
-      "embeds": [url],
+      "embeds": embeds,
       "channelKey": self.warpcast_channel_key,
       "castDistribution": "channel-only"
     });
 
     self.make_http_request(request_data).await.map_err(|e| {
       error!("Failed to make HTTP request: {}", e);
-      return e;
+      e
     })?;
 
     Ok(())
   }
+
+  async fn handle_new_top_bid(&self, collection: &Collection, old_top_bid: &TopBid) -> Result<()> {
+    let top_bid = collection
+      .top_bid
+      .as_ref()
+      .ok_or("Collection has no top bid")?;
+    info!("Handling new top bid: {}", top_bid.price.amount.decimal);
+    let now: DateTime<Utc> = Utc::now();
+
+    let amount = &top_bid.price.amount;
+
+    let url = format!(
+      "{}?{}",
+      marketplace_collection_url(&top_bid.source_domain, &collection.id, &collection.slug),
+      now.timestamp()
+    );
+
+    let description = format!(
+      "The top bid on the second market is now {} Ξ ({}) on {}, while the previous was {} Ξ.",
+      amount.decimal,
+      amount.format_usd(),
+      marketplace_name(&top_bid.source_domain),
+      old_top_bid.price.amount.decimal
+    );
+
+    let request_data = json!({
+      "text": description,
+      "embeds": [url],
+      "channelKey": self.warpcast_channel_key,
+      "castDistribution": "channel-only"
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_new_listing(&self, listing: &FloorAsk, old_floor_ask: &FloorAsk) -> Result<()> {
+    info!("Handling new listing: {}", listing.id);
+
+    let token = &listing.token;
+    let amount = &listing.price.amount;
+
+    let url = marketplace_url(&listing.source_domain, &token.contract, &token.token_id);
+    let seller = get_username_by_address(self.farquest_api_key.as_str(), &listing.maker).await;
+
+    let description = format!(
+      "{} was listed for {} Ξ ({}) on {} by {}, below the previous floor of {} Ξ.",
+      token.display_name(),
+      amount.decimal,
+      amount.format_usd(),
+      marketplace_name(&listing.source_domain),
+      seller,
+      old_floor_ask.price.amount.decimal
+    );
+
+    let mut embeds = vec![url];
+    if let Some(image) = &token.image {
+      embeds.push(image.clone());
+    }
+
+    let request_data = json!({
+      "text": description,
+      "embeds": embeds,
+      "channelKey": self.warpcast_channel_key,
+      "castDistribution": "channel-only"
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_market_summary(
+    &self,
+    collection: &Collection,
+    summary: &MarketSummary,
+  ) -> Result<()> {
+    info!("Handling market summary: {}", collection.name);
+    let now: DateTime<Utc> = Utc::now();
+
+    let url = format!(
+      "https://blur.io/eth/collection/{}?{}",
+      collection.slug,
+      now.timestamp()
+    );
+    let format_eth = |value: Option<f64>| match value {
+      Some(value) => format!("{:.4} Ξ", value),
+      None => "n/a".to_string(),
+    };
+
+    let description = format!(
+      "Daily second market recap\n\nFloor: {} → {} ({})\nVolume: {} ({})\nSales: {}\nOwners: \
+       {}\n\nPast week: {}",
+      format_eth(summary.floor_open),
+      format_eth(Some(summary.floor_close.decimal)),
+      summary.floor_close.format_usd(),
+      format_eth(summary.volume),
+      FloorChange::format_percent(summary.volume_change),
+      summary.sales,
+      summary
+        .owner_count
+        .map_or("n/a".to_string(), |count| count.to_string()),
+      summary.sparkline
+    );
+
+    let request_data = json!({
+      "text": description,
+      "embeds": [url],
+      "channelKey": self.warpcast_channel_key,
+      "castDistribution": "channel-only"
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
+
+  async fn handle_new_sale(&self, sale: &Sale) -> Result<()> {
+    info!("Handling new sale: {}", sale.sale_id);
+
+    let source = sale.order_source.clone().unwrap_or_default();
+    let url = marketplace_url(&source, &sale.token.contract, &sale.token.token_id);
+    let name = sale.token.display_name();
+    let seller = get_username_by_address(self.farquest_api_key.as_str(), &sale.from).await;
+    let buyer = get_username_by_address(self.farquest_api_key.as_str(), &sale.to).await;
+
+    let description = format!(
+      "{} was sold for {} Ξ ({}) on {}.\n\nSeller: {}\nBuyer: {}",
+      name,
+      sale.price.amount.decimal,
+      sale.price.amount.format_usd(),
+      marketplace_name(&source),
+      seller,
+      buyer
+    );
+
+    let mut embeds = vec![url];
+    if let Some(image) = &sale.token.image {
+      embeds.push(image.clone());
+    }
+
+    let request_data = json!({
+      "text": description,
+      "embeds": embeds,
+      "channelKey": self.warpcast_channel_key,
+      "castDistribution": "channel-only"
+    });
+
+    self.make_http_request(request_data).await?;
+
+    Ok(())
+  }
 }
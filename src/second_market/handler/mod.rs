@@ -1,12 +1,24 @@
 use async_trait::async_trait;
 use worker::Result;
 
-use crate::second_market::fetcher::Collection;
+use crate::second_market::{
+  fetcher::{Collection, FloorAsk, Sale, TopBid},
+  FloorChange,
+  MarketSummary,
+};
 
 pub(crate) mod discord;
 pub(crate) mod farcaster;
 
 #[async_trait(? Send)]
 pub trait Handler {
-  async fn handle_new_floor(&self, collection: &Collection) -> Result<()>;
+  async fn handle_new_floor(&self, collection: &Collection, change: &FloorChange) -> Result<()>;
+  async fn handle_new_sale(&self, sale: &Sale) -> Result<()>;
+  async fn handle_market_summary(
+    &self,
+    collection: &Collection,
+    summary: &MarketSummary,
+  ) -> Result<()>;
+  async fn handle_new_top_bid(&self, collection: &Collection, old_top_bid: &TopBid) -> Result<()>;
+  async fn handle_new_listing(&self, listing: &FloorAsk, old_floor_ask: &FloorAsk) -> Result<()>;
 }
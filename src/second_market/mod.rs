@@ -1,3 +1,4 @@
+use chrono::{Duration, Utc};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use worker::{Env, Result};
@@ -5,13 +6,14 @@ use worker::{Env, Result};
 use crate::{
   cache::Cache,
   second_market::{
-    fetcher::{Collection, RestFetcher},
+    fetcher::{Amount, Collection, FloorAsk, RestFetcher, Sale, TopBid},
     handler::{discord::DiscordHandler, farcaster::FarcasterHandler, Handler},
   },
 };
 
 pub(crate) mod fetcher;
 mod handler;
+mod url;
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct Floor {
@@ -22,18 +24,151 @@ pub struct Floor {
   pub created_at: String,
 }
 
+/// How long floor prices are kept in the history.
+const FLOOR_HISTORY_WINDOW: Duration = Duration::days(8);
+
+/// How much later than asked a history point may be recorded and still
+/// count, since cron runs drift by a few seconds from day to day.
+const FLOOR_HISTORY_TOLERANCE: Duration = Duration::hours(1);
+
+/// Bars used to draw floor sparklines, from lowest to highest.
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A floor price recorded at a point in time.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct FloorPoint {
+  pub timestamp: i64,
+  pub price: f64,
+  pub usd: Option<f64>,
+}
+
+impl FloorPoint {
+  /// Returns the latest point recorded at or before the given time.
+  fn find_at(history: &[FloorPoint], timestamp: i64) -> Option<&FloorPoint> {
+    history
+      .iter()
+      .rev()
+      .find(|point| point.timestamp <= timestamp + FLOOR_HISTORY_TOLERANCE.num_seconds())
+  }
+}
+
+/// The minimum price change that triggers an alert, either in percent of
+/// the last alerted price or as an absolute amount in ETH.
+#[derive(Clone, Copy, Debug)]
+pub enum PriceThreshold {
+  Percent(f64),
+  Absolute(f64),
+}
+
+impl PriceThreshold {
+  /// Parses values like `5%` or `0.05`. An empty value alerts on any change.
+  pub fn parse(value: &str) -> Option<Self> {
+    let value = value.trim();
+    if value.is_empty() {
+      return Some(Self::Absolute(0.0));
+    }
+
+    match value.strip_suffix('%') {
+      Some(percent) => percent.trim().parse().ok().map(Self::Percent),
+      None => value.parse().ok().map(Self::Absolute),
+    }
+  }
+
+  pub fn is_exceeded(&self, old_price: f64, new_price: f64) -> bool {
+    let difference = (new_price - old_price).abs();
+    if difference == 0.0 {
+      return false;
+    }
+
+    match *self {
+      Self::Percent(percent) => old_price == 0.0 || difference / old_price * 100.0 >= percent,
+      Self::Absolute(amount) => difference >= amount,
+    }
+  }
+}
+
+/// How the floor moved since the last alert and over the past day and week.
+#[derive(Clone, Debug)]
+pub struct FloorChange {
+  pub old_price: f64,
+  pub day: Option<f64>,
+  pub week: Option<f64>,
+}
+
+impl FloorChange {
+  fn new(history: &[FloorPoint], old_price: f64, price: f64, now: i64) -> Self {
+    let change_since = |duration: Duration| {
+      FloorPoint::find_at(history, now - duration.num_seconds())
+        .filter(|point| point.price > 0.0)
+        .map(|point| (price - point.price) / point.price * 100.0)
+    };
+
+    Self {
+      old_price,
+      day: change_since(Duration::days(1)),
+      week: change_since(Duration::days(7)),
+    }
+  }
+
+  pub fn format_percent(change: Option<f64>) -> String {
+    match change {
+      Some(change) => format!("{:+.2}%", change),
+      None => "n/a".to_string(),
+    }
+  }
+}
+
+/// A recap of the market over the past day.
+#[derive(Clone, Debug)]
+pub struct MarketSummary {
+  pub floor_open: Option<f64>,
+  pub floor_close: Amount,
+  pub volume: Option<f64>,
+  pub volume_change: Option<f64>,
+  pub sales: usize,
+  pub owner_count: Option<u64>,
+  pub sparkline: String,
+}
+
+impl MarketSummary {
+  /// Draws one bar per price, scaled between the lowest and highest one.
+  fn sparkline(prices: &[f64]) -> String {
+    let min = prices.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = prices.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let top = SPARKLINE_BARS.len() - 1;
+
+    prices
+      .iter()
+      .map(|price| match max > min {
+        true => SPARKLINE_BARS[((price - min) / (max - min) * top as f64).round() as usize],
+        false => SPARKLINE_BARS[top / 2],
+      })
+      .collect()
+  }
+}
+
 pub struct SecondMarket {
   cache: Cache,
   fetcher: RestFetcher,
   handlers: Vec<Box<dyn Handler>>,
+  floor_threshold: PriceThreshold,
+  top_bid_threshold: PriceThreshold,
 }
 
 impl SecondMarket {
-  pub fn new(cache: Cache, fetcher: RestFetcher, handlers: Vec<Box<dyn Handler>>) -> Self {
+  pub fn new(
+    cache: Cache,
+    fetcher: RestFetcher,
+    handlers: Vec<Box<dyn Handler>>,
+    floor_threshold: PriceThreshold,
+    top_bid_threshold: PriceThreshold,
+  ) -> Self {
     Self {
       cache,
       fetcher,
       handlers,
+      floor_threshold,
+      top_bid_threshold,
     }
   }
 
@@ -52,40 +187,253 @@ impl SecondMarket {
       handlers.push(farcaster_handler);
     }
 
-    Ok(Self::new(cache, fetcher, handlers))
+    let floor_threshold =
+      PriceThreshold::parse(&env.var("SECOND_MARKET_FLOOR_THRESHOLD")?.to_string())
+        .ok_or("Invalid SECOND_MARKET_FLOOR_THRESHOLD value")?;
+
+    let top_bid_threshold =
+      PriceThreshold::parse(&env.var("SECOND_MARKET_TOP_BID_THRESHOLD")?.to_string())
+        .ok_or("Invalid SECOND_MARKET_TOP_BID_THRESHOLD value")?;
+
+    Ok(Self::new(
+      cache,
+      fetcher,
+      handlers,
+      floor_threshold,
+      top_bid_threshold,
+    ))
   }
 
   pub async fn setup(&self) {
     debug!("Setup function started.");
 
     if !self.cache.has("second_market:collections").await {
-      if let Some(collections) = self.fetcher.fetch_collections().await {
-        info!("Fetched {:?} collections.", collections.len());
-        debug!("Putting fetched collections into cache.");
-        self
-          .cache
-          .put("second_market:collections", &collections)
-          .await;
-      } else {
-        warn!("Failed to fetch collections");
+      match self.fetcher.fetch_collections().await {
+        Ok(collections) => {
+          info!("Fetched {:?} collections.", collections.len());
+          debug!("Putting fetched collections into cache.");
+          self
+            .cache
+            .put("second_market:collections", &collections)
+            .await;
+        }
+        Err(e) => warn!("Failed to fetch collections: {}", e),
+      }
+    }
+
+    if !self.cache.has("second_market:sales").await {
+      match self.fetcher.fetch_sales().await {
+        Ok(sales) => {
+          info!("Fetched {:?} sales.", sales.len());
+          debug!("Putting fetched sale ids into cache.");
+          let ids: Vec<_> = sales.iter().map(|sale| sale.sale_id.clone()).collect();
+          self.cache.put("second_market:sales", &ids).await;
+        }
+        Err(e) => warn!("Failed to fetch sales: {}", e),
       }
     }
 
     debug!("Setup function finished.");
   }
 
-  pub async fn start(&self) -> Result<()> {
+  pub async fn check_sales(&self) -> Result<()> {
     self.setup().await;
 
-    debug!("Start function started.");
+    debug!("Sales function started.");
 
-    let new_collections = match self.fetcher.fetch_collections().await {
-      Some(collections) => collections,
-      None => {
-        debug!("Failed to fetch new collections.");
-        return Ok(());
+    let sales = self.fetcher.fetch_sales().await?;
+    debug!("Fetched {:?} sales.", sales.len());
+
+    let Some(old_ids) = self.cache.get::<Vec<String>>("second_market:sales").await? else {
+      debug!("No old sales found in the cache.");
+      return Ok(());
+    };
+
+    // Sales come newest first, so handle them in reverse to post in order.
+    let new_sales: Vec<&Sale> = sales
+      .iter()
+      .rev()
+      .filter(|sale| !old_ids.contains(&sale.sale_id))
+      .collect();
+
+    debug!("Found {:?} new sales.", new_sales.len());
+
+    for sale in &new_sales {
+      info!("Handling a new sale... ({:?})", sale.sale_id);
+      for handler in &self.handlers {
+        if let Err(err) = handler.handle_new_sale(sale).await {
+          error!("Failed to handle new sale: {:?}", err);
+        } else {
+          debug!("Successfully handled new sale: {:?}", sale.sale_id);
+        }
+      }
+    }
+
+    if !new_sales.is_empty() {
+      let ids: Vec<_> = sales.iter().map(|sale| sale.sale_id.clone()).collect();
+      self.cache.put("second_market:sales", &ids).await;
+      info!("Updated sales in cache");
+    }
+
+    debug!("Sales function finished.");
+
+    Ok(())
+  }
+
+  pub async fn check_orders(&self) -> Result<()> {
+    debug!("Orders function started.");
+
+    let Some(collection) = self.fetcher.fetch_collections().await?.into_iter().next() else {
+      warn!("No collection found");
+      return Ok(());
+    };
+
+    match (
+      &collection.top_bid,
+      self.cache.get::<TopBid>("second_market:top_bid").await?,
+    ) {
+      (None, _) => debug!("Collection has no top bid."),
+      (Some(top_bid), Some(old_top_bid))
+        if !self.top_bid_threshold.is_exceeded(
+          old_top_bid.price.amount.decimal,
+          top_bid.price.amount.decimal,
+        ) =>
+      {
+        debug!("Top bid has not changed enough.");
+      }
+      (Some(top_bid), Some(old_top_bid)) => {
+        info!("Handling a new top bid... ({:?})", top_bid.id);
+        for handler in &self.handlers {
+          if let Err(err) = handler.handle_new_top_bid(&collection, &old_top_bid).await {
+            error!("Failed to handle new top bid: {:?}", err);
+          } else {
+            debug!("Successfully handled new top bid: {:?}", top_bid.id);
+          }
+        }
+        self.cache.put("second_market:top_bid", top_bid).await;
       }
+      (Some(top_bid), None) => self.cache.put("second_market:top_bid", top_bid).await,
+    }
+
+    let Some(floor_ask) = &collection.floor_ask else {
+      debug!("Collection has no floor ask.");
+      return Ok(());
     };
+    let old_floor_ask = self
+      .cache
+      .get::<FloorAsk>("second_market:floor_ask")
+      .await?;
+    let seen_ids = self.cache.get::<Vec<String>>("second_market:asks").await?;
+    let asks = self.fetcher.fetch_asks().await?;
+
+    // Every unseen listing under the previous floor is announced, not only
+    // the one that became the new floor.
+    if let (Some(old_floor_ask), Some(seen_ids)) = (&old_floor_ask, &seen_ids) {
+      let new_listings: Vec<FloorAsk> = asks
+        .iter()
+        .filter(|ask| {
+          !seen_ids.contains(&ask.id)
+            && ask.price.amount.decimal < old_floor_ask.price.amount.decimal
+        })
+        .cloned()
+        .map(FloorAsk::from)
+        .collect();
+
+      debug!("Found {:?} new listings below floor.", new_listings.len());
+
+      for listing in &new_listings {
+        info!("Handling a new listing... ({:?})", listing.id);
+        for handler in &self.handlers {
+          if let Err(err) = handler.handle_new_listing(listing, old_floor_ask).await {
+            error!("Failed to handle new listing: {:?}", err);
+          } else {
+            debug!("Successfully handled new listing: {:?}", listing.id);
+          }
+        }
+      }
+    }
+
+    let ask_ids: Vec<_> = asks.iter().map(|ask| ask.id.clone()).collect();
+    self.cache.put("second_market:asks", &ask_ids).await;
+    self.cache.put("second_market:floor_ask", floor_ask).await;
+
+    debug!("Orders function finished.");
+
+    Ok(())
+  }
+
+  pub async fn post_summary(&self) -> Result<()> {
+    debug!("Summary function started.");
+
+    let Some(collection) = self.fetcher.fetch_collections().await?.into_iter().next() else {
+      warn!("No collection found");
+      return Ok(());
+    };
+    let Some(floor_ask) = &collection.floor_ask else {
+      debug!("Collection has no floor ask.");
+      return Ok(());
+    };
+
+    let now = Utc::now().timestamp();
+    let sales = self
+      .fetcher
+      .fetch_sales_since(now - Duration::days(1).num_seconds())
+      .await?;
+    let history = self
+      .cache
+      .get::<Vec<FloorPoint>>("second_market:floor:history")
+      .await?
+      .unwrap_or_default();
+
+    let mut prices: Vec<_> = history
+      .iter()
+      .filter(|point| point.timestamp >= now - Duration::days(7).num_seconds())
+      .map(|point| point.price)
+      .collect();
+    // The floor check may have already recorded the current floor.
+    if history.last().map_or(true, |point| {
+      point.timestamp < now - FLOOR_HISTORY_TOLERANCE.num_seconds()
+    }) {
+      prices.push(floor_ask.price.amount.decimal);
+    }
+
+    let summary = MarketSummary {
+      floor_open: FloorPoint::find_at(&history, now - Duration::days(1).num_seconds())
+        .map(|point| point.price),
+      floor_close: floor_ask.price.amount.clone(),
+      volume: collection.volume.get("1day").cloned().flatten(),
+      // Reservoir reports the volume change as a ratio to the previous day.
+      volume_change: collection
+        .volume_change
+        .get("1day")
+        .cloned()
+        .flatten()
+        .map(|ratio| (ratio - 1.0) * 100.0),
+      sales: sales.len(),
+      owner_count: collection.owner_count,
+      sparkline: MarketSummary::sparkline(&prices),
+    };
+
+    info!("Handling market summary...");
+    for handler in &self.handlers {
+      if let Err(err) = handler.handle_market_summary(&collection, &summary).await {
+        error!("Failed to handle market summary: {:?}", err);
+      } else {
+        debug!("Successfully handled market summary.");
+      }
+    }
+
+    debug!("Summary function finished.");
+
+    Ok(())
+  }
+
+  pub async fn start(&self) -> Result<()> {
+    self.setup().await;
+
+    debug!("Start function started.");
+
+    let new_collections = self.fetcher.fetch_collections().await?;
     debug!("Fetched {:?} collections.", new_collections.len());
 
     let old_collections = match self
@@ -100,44 +448,174 @@ impl SecondMarket {
       }
     };
 
-    if let (Some(old_collection), Some(new_collection)) =
-      (old_collections.get(0), new_collections.get(0))
-    {
-      if old_collection.floor_ask.price.amount.decimal
-        != new_collection.floor_ask.price.amount.decimal
-      {
-        info!("Handle a new floor...");
+    let (Some(old_collection), Some(new_collection)) =
+      (old_collections.first(), new_collections.first())
+    else {
+      debug!("Unable to compare floors: One of the collections is empty.");
+      return Ok(());
+    };
 
-        for handler in &self.handlers {
-          if let Err(err) = handler.handle_new_floor(new_collection).await {
-            error!("Failed to handle new floor: {:?}", err);
-          } else {
-            debug!("Successfully handled new floor.");
-          }
-        }
+    let Some(floor_ask) = &new_collection.floor_ask else {
+      debug!("Collection has no floor ask.");
+      return Ok(());
+    };
 
-        self
-          .cache
-          .put::<String>(
-            "second_market:old_price",
-            &new_collection.floor_ask.price.amount.decimal.to_string(),
-          )
-          .await;
-
-        self
-          .cache
-          .put("second_market:collections", &new_collections)
-          .await;
-        info!("Updated collections in cache");
-      } else {
-        debug!("Floor has not changed.");
+    let now = Utc::now().timestamp();
+    let amount = &floor_ask.price.amount;
+
+    let mut history = self
+      .cache
+      .get::<Vec<FloorPoint>>("second_market:floor:history")
+      .await?
+      .unwrap_or_default();
+
+    // The last alerted floor, falling back to the last fetched one.
+    let old_price = match self.cache.get::<f64>("second_market:old_price").await {
+      Ok(Some(price)) => price,
+      _ => old_collection
+        .floor_ask
+        .as_ref()
+        .map_or(amount.decimal, |old_floor_ask| {
+          old_floor_ask.price.amount.decimal
+        }),
+    };
+    let change = FloorChange::new(&history, old_price, amount.decimal, now);
+
+    history.push(FloorPoint {
+      timestamp: now,
+      price: amount.decimal,
+      usd: amount.usd,
+    });
+    history.retain(|point| point.timestamp >= now - FLOOR_HISTORY_WINDOW.num_seconds());
+    self
+      .cache
+      .put("second_market:floor:history", &history)
+      .await;
+
+    if self.floor_threshold.is_exceeded(old_price, amount.decimal) {
+      info!("Handle a new floor...");
+
+      for handler in &self.handlers {
+        if let Err(err) = handler.handle_new_floor(new_collection, &change).await {
+          error!("Failed to handle new floor: {:?}", err);
+        } else {
+          debug!("Successfully handled new floor.");
+        }
       }
+
+      self
+        .cache
+        .put("second_market:old_price", &amount.decimal)
+        .await;
     } else {
-      debug!("Unable to compare floors: One of the collections is empty.");
+      debug!("Floor has not changed enough.");
     }
 
+    self
+      .cache
+      .put("second_market:collections", &new_collections)
+      .await;
+    info!("Updated collections in cache");
+
     debug!("Start function finished.");
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn point(timestamp: i64, price: f64) -> FloorPoint {
+    FloorPoint {
+      timestamp,
+      price,
+      usd: None,
+    }
+  }
+
+  #[test]
+  fn parses_price_thresholds() {
+    assert!(matches!(
+      PriceThreshold::parse(""),
+      Some(PriceThreshold::Absolute(amount)) if amount == 0.0
+    ));
+    assert!(matches!(
+      PriceThreshold::parse(" 5 % "),
+      Some(PriceThreshold::Percent(percent)) if percent == 5.0
+    ));
+    assert!(matches!(
+      PriceThreshold::parse("0.05"),
+      Some(PriceThreshold::Absolute(amount)) if amount == 0.05
+    ));
+    assert!(PriceThreshold::parse("five").is_none());
+    assert!(PriceThreshold::parse("%").is_none());
+  }
+
+  #[test]
+  fn checks_price_thresholds() {
+    let percent = PriceThreshold::Percent(5.0);
+    assert!(percent.is_exceeded(1.0, 1.05));
+    assert!(percent.is_exceeded(1.0, 0.9));
+    assert!(!percent.is_exceeded(1.0, 1.04));
+    assert!(percent.is_exceeded(0.0, 0.1));
+    assert!(!percent.is_exceeded(0.0, 0.0));
+
+    let absolute = PriceThreshold::Absolute(0.1);
+    assert!(absolute.is_exceeded(1.0, 1.1));
+    assert!(!absolute.is_exceeded(1.0, 1.05));
+
+    let any = PriceThreshold::Absolute(0.0);
+    assert!(any.is_exceeded(1.0, 1.0001));
+    assert!(!any.is_exceeded(1.0, 1.0));
+  }
+
+  #[test]
+  fn finds_history_points_with_tolerance() {
+    let day = Duration::days(1).num_seconds();
+    let history = vec![point(0, 1.0), point(day, 2.0), point(2 * day + 60, 3.0)];
+
+    assert_eq!(
+      FloorPoint::find_at(&history, day).map(|p| p.price),
+      Some(2.0)
+    );
+    assert_eq!(
+      FloorPoint::find_at(&history, 2 * day).map(|p| p.price),
+      Some(3.0)
+    );
+    assert_eq!(
+      FloorPoint::find_at(&history, day - Duration::hours(2).num_seconds()).map(|p| p.price),
+      Some(1.0)
+    );
+    assert!(FloorPoint::find_at(&history, -day).is_none());
+    assert!(FloorPoint::find_at(&[], day).is_none());
+  }
+
+  #[test]
+  fn computes_floor_changes() {
+    let day = Duration::days(1).num_seconds();
+    let now = 7 * day;
+    let history = vec![point(0, 0.5), point(6 * day, 0.8)];
+
+    let change = FloorChange::new(&history, 0.9, 1.0, now);
+    assert_eq!(change.old_price, 0.9);
+    assert_eq!(change.day.map(|day| day.round()), Some(25.0));
+    assert_eq!(change.week.map(|week| week.round()), Some(100.0));
+
+    let change = FloorChange::new(&[point(0, 0.0)], 0.0, 1.0, now);
+    assert!(change.day.is_none());
+    assert!(change.week.is_none());
+
+    assert_eq!(FloorChange::format_percent(Some(12.345)), "+12.35%");
+    assert_eq!(FloorChange::format_percent(None), "n/a");
+  }
+
+  #[test]
+  fn draws_sparklines() {
+    assert_eq!(MarketSummary::sparkline(&[]), "");
+    assert_eq!(MarketSummary::sparkline(&[1.0]), "▄");
+    assert_eq!(MarketSummary::sparkline(&[1.0, 1.0, 1.0]), "▄▄▄");
+    assert_eq!(MarketSummary::sparkline(&[1.0, 2.0, 1.5]), "▁█▅");
+  }
+}
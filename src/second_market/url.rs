@@ -0,0 +1,94 @@
+/// Returns the page of a token on the marketplace with the given domain,
+/// or the marketplace home page when its token pages are unknown.
+pub fn marketplace_url(source_domain: &str, contract: &str, token_id: &str) -> String {
+  match source_domain {
+    "opensea.io" => format!(
+      "https://opensea.io/assets/ethereum/{}/{}",
+      contract, token_id
+    ),
+    "blur.io" => format!("https://blur.io/eth/asset/{}/{}", contract, token_id),
+    "looksrare.org" => format!(
+      "https://looksrare.org/collections/{}/{}",
+      contract, token_id
+    ),
+    "x2y2.io" => format!("https://x2y2.io/eth/{}/{}", contract, token_id),
+    "magiceden.io" => format!(
+      "https://magiceden.io/item-details/ethereum/{}/{}",
+      contract, token_id
+    ),
+    "" => format!("https://etherscan.io/nft/{}/{}", contract, token_id),
+    domain => format!("https://{}", domain),
+  }
+}
+
+/// Returns the collection page on the marketplace with the given domain, for
+/// orders such as collection bids that are not tied to one token.
+pub fn marketplace_collection_url(source_domain: &str, contract: &str, slug: &str) -> String {
+  match source_domain {
+    "opensea.io" => format!("https://opensea.io/collection/{}", slug),
+    "blur.io" => format!("https://blur.io/collection/{}", slug),
+    "looksrare.org" => format!("https://looksrare.org/collections/{}", contract),
+    "x2y2.io" => format!("https://x2y2.io/collection/{}", slug),
+    "magiceden.io" => format!("https://magiceden.io/collections/ethereum/{}", contract),
+    "" => format!("https://etherscan.io/token/{}", contract),
+    domain => format!("https://{}", domain),
+  }
+}
+
+/// Returns a readable marketplace name for a source domain.
+pub fn marketplace_name(source_domain: &str) -> String {
+  match source_domain {
+    "opensea.io" => "OpenSea".to_string(),
+    "blur.io" => "Blur".to_string(),
+    "looksrare.org" => "LooksRare".to_string(),
+    "x2y2.io" => "X2Y2".to_string(),
+    "magiceden.io" => "Magic Eden".to_string(),
+    "" => "an unknown marketplace".to_string(),
+    domain => domain.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn builds_marketplace_urls() {
+    let contract = "0x4b10701bfd7bfedc47d50562b76b436fbb5bdb3b";
+
+    assert_eq!(
+      marketplace_url("opensea.io", contract, "42"),
+      "https://opensea.io/assets/ethereum/0x4b10701bfd7bfedc47d50562b76b436fbb5bdb3b/42"
+    );
+    assert_eq!(
+      marketplace_url("blur.io", contract, "42"),
+      "https://blur.io/eth/asset/0x4b10701bfd7bfedc47d50562b76b436fbb5bdb3b/42"
+    );
+    assert_eq!(
+      marketplace_url("", contract, "42"),
+      "https://etherscan.io/nft/0x4b10701bfd7bfedc47d50562b76b436fbb5bdb3b/42"
+    );
+    assert_eq!(
+      marketplace_url("example.xyz", contract, "42"),
+      "https://example.xyz"
+    );
+  }
+
+  #[test]
+  fn builds_marketplace_collection_urls() {
+    let contract = "0x4b10701bfd7bfedc47d50562b76b436fbb5bdb3b";
+
+    assert_eq!(
+      marketplace_collection_url("opensea.io", contract, "lil-nouns"),
+      "https://opensea.io/collection/lil-nouns"
+    );
+    assert_eq!(
+      marketplace_collection_url("blur.io", contract, "lil-nouns"),
+      "https://blur.io/collection/lil-nouns"
+    );
+    assert_eq!(
+      marketplace_collection_url("", contract, "lil-nouns"),
+      "https://etherscan.io/token/0x4b10701bfd7bfedc47d50562b76b436fbb5bdb3b"
+    );
+  }
+}
@@ -46,3 +46,12 @@ pub async fn get_transaction_signer(tx_hash: &str) -> Result<Address> {
 
   Ok(from_address)
 }
+
+pub async fn get_block_number() -> Result<u64> {
+  // Initialize provider
+  let provider = create_provider().await?;
+
+  // Fetch the latest block number
+  let block_number = provider.get_block_number().await?;
+  Ok(block_number.as_u64())
+}